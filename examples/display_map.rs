@@ -27,11 +27,22 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             config: LdtkMapConfig {
                 set_clear_color: true,
                 scale: 1.0,
-                level: std::env::args()
-                    .nth(2)
-                    .map(|x| x.parse().unwrap())
-                    .unwrap_or(0),
+                // Select the level by index if the argument is a number, or by identifier
+                levels: LevelSelection::Single(
+                    std::env::args()
+                        .nth(2)
+                        .map(|x| match x.parse() {
+                            Ok(index) => LevelSelector::Index(index),
+                            Err(_) => LevelSelector::Identifier(x),
+                        })
+                        .unwrap_or_default(),
+                ),
                 center_map: false,
+                render_int_grid: false,
+                layer_z: LayerZStrategy::Step(1.0),
+                sublayer_z_step: 0.00001,
+                y_sort: None,
+                chunk_size: None,
             },
             ..Default::default()
         });
@@ -83,70 +94,89 @@ fn camera_movement(
     }
 }
 
+/// A marker for the player sprite
+struct Player;
+
 /// This system demonstrates how to get information out of the map, such as entity locations, and
 /// spawn a sprite at the location of the entity
+#[allow(clippy::too_many_arguments)]
 fn spawn_player(
     mut commands: Commands,
-    printed_maps: Local<Vec<Entity>>,
-    query: Query<(Entity, &Handle<LdtkMap>)>,
+    players: Query<(), With<Player>>,
+    maps: Query<(Entity, &Handle<LdtkMap>)>,
+    levels: Query<(Entity, &LdtkMapLevel, &Parent)>,
     map_assets: Res<Assets<LdtkMap>>,
+    map_geometry: LdtkMapGeometry,
     asset_server: Res<AssetServer>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    for (ent, handle) in query.iter() {
-        // Skip any maps we have already printed the spawn location for
-        if printed_maps.contains(&ent) {
-            continue;
-        }
+    // Only spawn the player once, even if the map is re-spawned
+    if players.iter().next().is_some() {
+        return;
+    }
 
-        // If the map asset has finished loading
-        if let Some(map) = map_assets.get(handle) {
-            // This is the default level, but if you spawned a different level, put that ID here
-            let level_idx = 0;
-
-            // Get the level from the project
-            let level = &map.project.levels[level_idx];
-
-            // Find the entities layer
-            let entities_layer = level
-                .layer_instances
-                .as_ref() // get a reference to the layer instances
-                .unwrap() // Unwrap the option ( this could be None, if there are no layers )
-                .iter() // Iterate over the layers
-                .filter(|&x| x.__identifier == "Entities") // Filter on the name of the layer
-                .next() // Get it
-                .unwrap(); // Unwrap it ( would be None if it could not find a layer "MyEntities" )
-
-            // Get the specific entity you want
-            let player_start = entities_layer
-                .entity_instances
-                .iter() // Iterate over our entities in the layer
-                .filter(|x| x.__identifier == "Player_Spawn") // Find the one we want
-                .next() // Get it
-                .unwrap(); // Unwrap it
-
-            // Get the number of layers in the map and add one to it: this is how high we need to
-            // spawn the player so that he is on top of all the maps
-            let player_z = level.layer_instances.as_ref().unwrap().len() as f32 + 1.0;
-
-            // Spawn the entity!
-            commands.spawn().insert_bundle(SpriteBundle {
+    for (map_ent, map_handle) in maps.iter() {
+        // Wait for the map asset to load
+        let map = match map_assets.get(map_handle) {
+            Some(map) => map,
+            None => continue,
+        };
+
+        // Wait for the first level of the map to be spawned
+        let (level_ent, map_level) = match levels
+            .iter()
+            .filter(|(_, _, parent)| parent.0 == map_ent)
+            .min_by_key(|(_, level, _)| level.index)
+        {
+            Some((level_ent, map_level, _)) => (level_ent, map_level),
+            None => continue,
+        };
+        let level = &map.project.levels[map_level.index];
+
+        // Find the entities layer
+        let entities_layer = level
+            .layer_instances
+            .as_ref() // get a reference to the layer instances
+            .unwrap() // Unwrap the option ( this could be None, if there are no layers )
+            .iter() // Iterate over the layers
+            .find(|&x| x.__identifier == "Entities") // Find the layer by its name
+            .unwrap(); // Unwrap it ( would be None if it could not find a layer "MyEntities" )
+
+        // Get the specific entity you want
+        let player_start = entities_layer
+            .entity_instances
+            .iter() // Iterate over our entities in the layer
+            .find(|x| x.__identifier == "Player_Spawn") // Find the one we want
+            .unwrap(); // Unwrap it
+
+        // Convert the entity's position in the map data to a world position. This takes care of
+        // the map's scale and centering, the layer's offset, and LDtk's +y meaning down.
+        let position = map_geometry
+            .level_layer(level_ent, "Entities")
+            .unwrap()
+            .ldtk_px_to_world(Vec2::new(
+                player_start.px[0] as f32,
+                player_start.px[1] as f32,
+            ));
+
+        // Place the player at the Z coordinate of the entities layer, so that it is drawn on
+        // top of the layers below it
+        let player_z = map_geometry.layer_z(map_ent, "Entities").unwrap();
+
+        // Spawn the entity!
+        commands
+            .spawn()
+            .insert_bundle(SpriteBundle {
                 // Set your sprite stuff
-                transform: Transform::from_xyz(
-                    // The player x position is the entity's x position from the map data
-                    player_start.px[0] as f32,
-                    // The player y position is the entity's y position from the map data, but
-                    // multiplied by negative one because in the LDtk map +y means down and not up.
-                    player_start.px[1] as f32 * -1.0,
-                    // Spawn the player with the z value we determined earlier
-                    player_z,
-                ),
+                transform: Transform::from_xyz(position.x, position.y, player_z),
                 material: color_materials.add(ColorMaterial {
                     texture: Some(asset_server.load("character.png")),
                     ..Default::default()
                 }),
                 ..Default::default()
-            });
-        }
+            })
+            .insert(Player);
+
+        return;
     }
 }
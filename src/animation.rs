@@ -0,0 +1,221 @@
+use bevy::utils::HashMap;
+
+use crate::LdtkTilemapLayer;
+
+/// A tile animation, described in the custom data of the first tile of the animation
+///
+/// LDtk doesn't support animated tiles, so the animation is described by adding an `animation`
+/// object to the tile's custom data in the tileset, listing the tile ids of the frames and how
+/// many seconds each frame is displayed for:
+///
+/// ```json
+/// { "animation": { "frames": [12, 13, 14], "durations": [0.1, 0.1, 0.2] } }
+/// ```
+///
+/// A single `duration` can be given instead of `durations` when all of the frames are displayed
+/// for the same amount of time. Every tile placed in the map with the animated tile id will be
+/// animated.
+///
+/// # Example
+///
+/// ```
+/// use bevy_ldtk::*;
+///
+/// let custom_data = r#"{ "animation": { "frames": [4, 5], "duration": 0.5 } }"#;
+/// let animation = LdtkTileAnimation::from_custom_data(custom_data).unwrap();
+///
+/// assert_eq!(animation.duration(), 1.0);
+/// assert_eq!(animation.frame_at(0.25), Some(4));
+/// assert_eq!(animation.frame_at(0.75), Some(5));
+/// assert_eq!(animation.frame_at(1.25), Some(4));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkTileAnimation {
+    /// The frames of the animation, in the order that they are displayed
+    pub frames: Vec<LdtkTileAnimationFrame>,
+}
+
+/// A single frame of an [`LdtkTileAnimation`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdtkTileAnimationFrame {
+    /// The id of the tile in the tileset to display
+    pub tile_id: u32,
+    /// The number of seconds that the frame is displayed for
+    pub duration: f32,
+}
+
+impl LdtkTileAnimation {
+    /// Parse an animation from the custom data of a tile, returning `None` if the custom data
+    /// doesn't describe a valid animation
+    ///
+    /// Animations without any frames, or with frames that aren't displayed for a positive number of
+    /// seconds, aren't valid.
+    pub fn from_custom_data(custom_data: &str) -> Option<Self> {
+        let data: serde_json::Value = serde_json::from_str(custom_data).ok()?;
+        let animation = data.get("animation")?;
+
+        let tile_ids = animation["frames"]
+            .as_array()?
+            .iter()
+            .map(|x| x.as_u64().map(|x| x as u32))
+            .collect::<Option<Vec<_>>>()?;
+
+        // Use the duration of each frame if it is given, otherwise use the same duration for every
+        // frame
+        let durations = match animation.get("durations") {
+            Some(durations) => durations
+                .as_array()?
+                .iter()
+                .map(|x| x.as_f64().map(|x| x as f32))
+                .collect::<Option<Vec<_>>>()?,
+            None => vec![animation["duration"].as_f64()? as f32; tile_ids.len()],
+        };
+
+        if tile_ids.is_empty()
+            || durations.len() != tile_ids.len()
+            || durations.iter().any(|&x| !(x > 0.0 && x.is_finite()))
+        {
+            return None;
+        }
+
+        Some(LdtkTileAnimation {
+            frames: tile_ids
+                .into_iter()
+                .zip(durations)
+                .map(|(tile_id, duration)| LdtkTileAnimationFrame { tile_id, duration })
+                .collect(),
+        })
+    }
+
+    /// Get the number of seconds that it takes to play the whole animation
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|x| x.duration).sum()
+    }
+
+    /// Get the id of the tile to display at the given number of seconds into the animation
+    ///
+    /// The animation loops, so any time past the end of the animation wraps back around to the
+    /// start. Animations that don't take any time stay on their first frame, and animations without
+    /// any frames return `None`. Neither can be parsed from custom data, but they can be made by
+    /// hand.
+    pub fn frame_at(&self, time: f32) -> Option<u32> {
+        let duration = self.duration();
+        if duration.is_nan() || duration <= 0.0 {
+            return self.frames.first().map(|x| x.tile_id);
+        }
+
+        let mut time = time.rem_euclid(duration);
+        for frame in &self.frames {
+            if time < frame.duration {
+                return Some(frame.tile_id);
+            }
+            time -= frame.duration;
+        }
+
+        // Floating point error can leave us just past the last frame
+        self.frames.last().map(|x| x.tile_id)
+    }
+
+    /// Get whether or not the given tile id is one of the frames of the animation
+    pub fn has_frame(&self, tile_id: u32) -> bool {
+        self.frames.iter().any(|x| x.tile_id == tile_id)
+    }
+}
+
+/// A component that controls the tile animations of a map, added to the map entity
+///
+/// Maps without this component play their tile animations at normal speed.
+#[derive(Debug, Clone)]
+pub struct LdtkTileAnimationConfig {
+    /// How fast to play the animations, with 1.0 being normal speed
+    pub speed: f32,
+    /// Whether or not the animations are paused
+    pub paused: bool,
+}
+
+impl Default for LdtkTileAnimationConfig {
+    fn default() -> Self {
+        LdtkTileAnimationConfig {
+            speed: 1.0,
+            paused: false,
+        }
+    }
+}
+
+/// A component added to rendered layer entities that have animated tiles
+///
+/// Tiles that are changed at runtime to a tile that isn't part of their animation stop being
+/// animated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkAnimatedTiles {
+    /// The uid of the layer's tileset
+    pub tileset_uid: i32,
+    /// The index in the layer's `tiles` buffer of every animated tile, along with the id of the
+    /// tile that its animation was found on
+    pub tiles: Vec<(usize, u32)>,
+}
+
+impl LdtkAnimatedTiles {
+    /// Find the animated tiles of a rendered layer, returning `None` if it doesn't have any
+    pub(crate) fn new(
+        tileset_uid: i32,
+        tilemap_layer: &LdtkTilemapLayer,
+        animations: &HashMap<u32, LdtkTileAnimation>,
+    ) -> Option<Self> {
+        let tiles = tilemap_layer
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| animations.contains_key(&tile.tile_index))
+            .map(|(i, tile)| (i, tile.tile_index))
+            .collect::<Vec<_>>();
+
+        if tiles.is_empty() {
+            None
+        } else {
+            Some(LdtkAnimatedTiles { tileset_uid, tiles })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(durations: &[f32]) -> LdtkTileAnimation {
+        LdtkTileAnimation {
+            frames: durations
+                .iter()
+                .enumerate()
+                .map(|(i, &duration)| LdtkTileAnimationFrame {
+                    tile_id: i as u32,
+                    duration,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn invalid_animations_are_rejected() {
+        for custom_data in &[
+            r#"{ "animation": { "frames": [], "duration": 0.5 } }"#,
+            r#"{ "animation": { "frames": [1, 2], "duration": 0 } }"#,
+            r#"{ "animation": { "frames": [1, 2], "durations": [0.5, 0] } }"#,
+            r#"{ "animation": { "frames": [1, 2], "durations": [0.5, -1] } }"#,
+            r#"{ "animation": { "frames": [1, 2], "durations": [0.5] } }"#,
+            r#"{ "animation": { "frames": [1, 2], "duration": 1e-50 } }"#,
+            r#"{ "animation": { "frames": [1, 2], "duration": 1e50 } }"#,
+        ] {
+            assert_eq!(LdtkTileAnimation::from_custom_data(custom_data), None);
+        }
+    }
+
+    #[test]
+    fn frame_at_handles_animations_without_duration() {
+        assert_eq!(animation(&[]).frame_at(1.0), None);
+        assert_eq!(animation(&[0.0, 0.0]).frame_at(0.0), Some(0));
+        assert_eq!(animation(&[0.0, 0.0]).frame_at(1.0), Some(0));
+        assert_eq!(animation(&[0.5, 0.5]).frame_at(-0.25), Some(1));
+        assert_eq!(animation(&[0.5, 0.5]).frame_at(f32::NAN), Some(1));
+    }
+}
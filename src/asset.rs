@@ -1,10 +1,13 @@
 use bevy::{
-    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    asset::{AssetIoError, AssetLoader, AssetPath, LoadContext, LoadedAsset},
     prelude::*,
     reflect::TypeUuid,
+    render::texture::{Extent3d, FilterMode, SamplerDescriptor, TextureDimension, TextureFormat},
     utils::{BoxedFuture, HashMap},
 };
 
+use crate::{LdtkParallax, LdtkTileAnimation, LdtkTilesetMeta};
+
 /// An LDtk map asset
 #[derive(TypeUuid)]
 #[uuid = "abd7b6d9-633f-4322-a8f4-e5f011cae9c6"]
@@ -13,12 +16,71 @@ pub struct LdtkMap {
     pub project: ldtk::Project,
     /// A mapping of Tileset identifiers to their texture handles
     pub tile_sets: HashMap<String, Handle<Texture>>,
+    /// A mapping of level uids to the handles of their external level files, for projects that
+    /// have the "save levels to separate files" option enabled
+    pub external_levels: HashMap<i32, Handle<LdtkLevel>>,
+    /// A mapping of IntGrid layer definition uids to generated palette textures containing the
+    /// colors of the IntGrid values, used to render IntGrid layers that don't have a tileset
+    pub int_grid_palettes: HashMap<i32, Handle<Texture>>,
+    /// A mapping of tileset uids to the custom data and enum tags of their tiles
+    pub tileset_meta: HashMap<i32, LdtkTilesetMeta>,
+    /// A mapping of tileset uids to the animations described in the custom data of their tiles,
+    /// indexed by the id of the tile that the animation is on
+    pub tile_animations: HashMap<i32, HashMap<u32, LdtkTileAnimation>>,
+    /// A mapping of layer definition uids to the parallax settings of the layers, for layers that
+    /// have parallax settings
+    pub layer_parallax: HashMap<i32, LdtkParallax>,
+    /// A mapping of level uids to the iids of the entity instances in the level's layers, indexed
+    /// by layer definition uid and listed in the same order as the layer's entity instances. Only
+    /// layers whose entity instances all have iids are included.
+    pub entity_iids: HashMap<i32, HashMap<i32, Vec<String>>>,
+    /// A mapping of level uids to the iids of the levels. Levels don't have iids in LDtk 0.8.1, so
+    /// only the levels that have one are included.
+    pub level_iids: HashMap<i32, String>,
+}
+
+/// An LDtk level asset, loaded from a separate `.ldtkl` level file
+#[derive(TypeUuid)]
+#[uuid = "1e8ae9c4-52e0-4d1a-9a5c-0a2ec1b2b1a7"]
+pub struct LdtkLevel {
+    /// The level data from the level file
+    pub level: ldtk::Level,
+    /// A mapping of layer definition uids to the iids of the entity instances in the layer, like
+    /// [`LdtkMap::entity_iids`]
+    pub entity_iids: HashMap<i32, Vec<String>>,
+}
+
+/// Read the iids of the entity instances in the layers of a level from the level JSON
+///
+/// Entity instances don't have iids in LDtk 0.8.1, so layers with entity instances that don't have
+/// iids are left out.
+fn level_entity_iids(level: &serde_json::Value) -> HashMap<i32, Vec<String>> {
+    level["layerInstances"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|layer| {
+            let instances = layer["entityInstances"].as_array()?;
+            if instances.is_empty() {
+                return None;
+            }
+
+            let iids = instances
+                .iter()
+                .map(|x| x["iid"].as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()?;
+
+            Some((layer["layerDefUid"].as_i64()? as i32, iids))
+        })
+        .collect()
 }
 
 /// Add asset types and asset loader to the app builder
 pub(crate) fn add_assets(app: &mut AppBuilder) {
     app.add_asset::<LdtkMap>()
-        .init_asset_loader::<LdtkMapLoader>();
+        .add_asset::<LdtkLevel>()
+        .init_asset_loader::<LdtkMapLoader>()
+        .init_asset_loader::<LdtkLevelLoader>();
 }
 
 /// An error that occurs when loading a GLTF file
@@ -26,6 +88,10 @@ pub(crate) fn add_assets(app: &mut AppBuilder) {
 pub enum LdtkMapLoaderError {
     #[error("Could not parese LDtk map file: {0}")]
     ParsingError(#[from] serde_json::Error),
+    #[error("Could not read external LDtk level file: {0}")]
+    ExternalLevelError(#[from] AssetIoError),
+    #[error("Invalid IntGrid value color: `{0}`")]
+    InvalidColor(String),
 }
 
 /// An LDTK map asset loader
@@ -56,12 +122,58 @@ async fn load_ldtk<'a, 'b>(
     // Deserialize the LDTK project file
     let project: ldtk::Project = serde_json::from_slice(bytes)?;
 
+    // Read the parts of the project that aren't in the LDtk 0.8.1 data structures from the raw JSON
+    let project_json: serde_json::Value = serde_json::from_slice(bytes)?;
+
     // Create a map asset
     let mut map = LdtkMap {
         project,
         tile_sets: Default::default(),
+        external_levels: Default::default(),
+        int_grid_palettes: Default::default(),
+        tileset_meta: project_json["defs"]["tilesets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(LdtkTilesetMeta::from_json)
+            .map(|x| (x.uid, x))
+            .collect(),
+        tile_animations: Default::default(),
+        layer_parallax: project_json["defs"]["layers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| Some((x["uid"].as_i64()? as i32, LdtkParallax::from_json(x)?)))
+            .collect(),
+        entity_iids: project_json["levels"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| Some((x["uid"].as_i64()? as i32, level_entity_iids(x))))
+            .collect(),
+        level_iids: project_json["levels"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| Some((x["uid"].as_i64()? as i32, x["iid"].as_str()?.to_string())))
+            .collect(),
     };
 
+    // Find the tiles that have animations in their custom data
+    for (tileset_uid, tileset_meta) in &map.tileset_meta {
+        let animations = tileset_meta
+            .custom_data
+            .iter()
+            .filter_map(|(&tile_id, data)| {
+                LdtkTileAnimation::from_custom_data(data).map(|x| (tile_id, x))
+            })
+            .collect::<HashMap<_, _>>();
+
+        if !animations.is_empty() {
+            map.tile_animations.insert(*tileset_uid, animations);
+        }
+    }
+
     // Create our dependency list
     let mut dependencies = Vec::new();
 
@@ -86,8 +198,112 @@ async fn load_ldtk<'a, 'b>(
         map.tile_sets.insert(tileset.identifier.clone(), handle);
     }
 
+    // Create a palette texture for every IntGrid layer definition, containing one pixel for the
+    // color of each of the IntGrid values
+    for layer_def in &map.project.defs.layers {
+        if layer_def.__type != "IntGrid" || layer_def.int_grid_values.is_empty() {
+            continue;
+        }
+
+        // Collect the pixel data for the palette
+        let mut data = Vec::with_capacity(layer_def.int_grid_values.len() * 4);
+        for value in &layer_def.int_grid_values {
+            let color = value
+                .color
+                .strip_prefix('#')
+                .and_then(|hex| Color::hex(hex).ok())
+                .ok_or_else(|| LdtkMapLoaderError::InvalidColor(value.color.clone()))?;
+
+            // The layer opacity is applied by the shader, like it is for every other layer
+            data.extend(color.as_rgba_f32().iter().map(|x| (x * 255.0) as u8));
+        }
+
+        // Create the palette texture, making sure that it is sampled without filtering
+        let mut palette = Texture::new(
+            Extent3d::new(layer_def.int_grid_values.len() as u32, 1, 1),
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        palette.sampler = SamplerDescriptor {
+            min_filter: FilterMode::Nearest,
+            mag_filter: FilterMode::Nearest,
+            ..Default::default()
+        };
+
+        // Add the palette as a labeled asset of the map
+        let handle = load_context.set_labeled_asset(
+            &format!("IntGridPalette{}", layer_def.uid),
+            LoadedAsset::new(palette),
+        );
+        map.int_grid_palettes.insert(layer_def.uid, handle);
+    }
+
+    // Loop through the levels
+    for level in &mut map.project.levels {
+        // Skip levels that are stored inside of the project file
+        let level_rel_path = if let Some(path) = &level.external_rel_path {
+            path
+        } else {
+            continue;
+        };
+
+        // Get the path to the external level file
+        let file_path = load_context.path().parent().unwrap().join(level_rel_path);
+        let asset_path = AssetPath::new(file_path.clone(), None);
+
+        // Read and parse the level file. We fill in the layer data for the level here so that the
+        // map is complete by the time it is loaded.
+        let level_bytes = load_context.read_asset_bytes(&file_path).await?;
+        let external_level: ldtk::Level = serde_json::from_slice(&level_bytes)?;
+        let external_level_json: serde_json::Value = serde_json::from_slice(&level_bytes)?;
+        level.layer_instances = external_level.layer_instances;
+        map.entity_iids
+            .insert(level.uid, level_entity_iids(&external_level_json));
+
+        // Add the level file to our dependencies so that the asset server will load it as an
+        // `LdtkLevel` and we can watch it for changes.
+        dependencies.push(asset_path.clone());
+
+        // Add the level handle to the map asset
+        map.external_levels
+            .insert(level.uid, load_context.get_handle(asset_path.clone()));
+    }
+
     // Set the loaded map as the default asset for this file
     load_context.set_default_asset(LoadedAsset::new(map).with_dependencies(dependencies));
 
     Ok(())
 }
+
+/// An LDtk level asset loader for levels stored in separate `.ldtkl` files
+#[derive(Default)]
+struct LdtkLevelLoader;
+
+impl AssetLoader for LdtkLevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            // Deserialize the level file
+            let level: ldtk::Level =
+                serde_json::from_slice(bytes).map_err(LdtkMapLoaderError::from)?;
+            let level_json: serde_json::Value =
+                serde_json::from_slice(bytes).map_err(LdtkMapLoaderError::from)?;
+
+            // Set the loaded level as the default asset for this file
+            load_context.set_default_asset(LoadedAsset::new(LdtkLevel {
+                level,
+                entity_iids: level_entity_iids(&level_json),
+            }));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ldtkl"]
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::LdtkIntGrid;
+
+/// A rectangle of cells in an IntGrid layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LdtkGridRect {
+    /// The x coordinate of the top-left cell of the rectangle
+    pub x: u32,
+    /// The y coordinate of the top-left cell of the rectangle
+    pub y: u32,
+    /// The number of cells wide the rectangle is
+    pub width: u32,
+    /// The number of cells tall the rectangle is
+    pub height: u32,
+}
+
+/// A collision rectangle, in world units relative to the IntGrid layer entity it is attached to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdtkColliderRect {
+    /// The center of the rectangle
+    pub center: Vec2,
+    /// Half of the width and height of the rectangle
+    pub half_extents: Vec2,
+}
+
+/// A component containing the merged collision rectangles for an IntGrid layer
+///
+/// This is added to the [`LdtkIntGrid`] layer entities for layers that have been registered with
+/// [`LdtkAppBuilderExt::register_ldtk_colliders`].
+///
+/// [`LdtkAppBuilderExt::register_ldtk_colliders`]: crate::LdtkAppBuilderExt::register_ldtk_colliders
+#[derive(Debug, Clone, Default)]
+pub struct LdtkColliders {
+    /// The merged rectangles, in grid cells
+    pub cells: Vec<LdtkGridRect>,
+    /// The merged rectangles, in world units relative to the layer entity. Because the layer entity
+    /// is placed at the top-left corner of the layer, these can be used directly as the shapes of
+    /// child colliders for a physics engine.
+    pub rects: Vec<LdtkColliderRect>,
+}
+
+impl LdtkColliders {
+    /// Create the colliders for the cells in an IntGrid layer that have one of the given values
+    pub fn from_int_grid(int_grid: &LdtkIntGrid, solid_values: &[i32]) -> Self {
+        let cells = merge_grid_rects(&int_grid.values, int_grid.width, int_grid.height, |value| {
+            solid_values.contains(&value)
+        });
+
+        let cell_size = int_grid.grid_size as f32 * int_grid.scale;
+        let rects = cells
+            .iter()
+            .map(|rect| {
+                let size = Vec2::new(rect.width as f32, rect.height as f32) * cell_size;
+                LdtkColliderRect {
+                    // The cells go down from the layer origin, so we flip the y axis
+                    center: Vec2::new(
+                        rect.x as f32 * cell_size + size.x / 2.0,
+                        -(rect.y as f32 * cell_size + size.y / 2.0),
+                    ),
+                    half_extents: size / 2.0,
+                }
+            })
+            .collect();
+
+        LdtkColliders { cells, rects }
+    }
+}
+
+/// Merge the solid cells of a grid into as few axis-aligned rectangles as possible
+///
+/// The `values` are stored from left to right, and top to bottom, and `is_solid` decides which
+/// values should be included in the rectangles. Rectangles are grown greedily: first as far right
+/// as possible, then as far down as possible.
+///
+/// # Example
+///
+/// ```
+/// use bevy_ldtk::*;
+///
+/// #[rustfmt::skip]
+/// let values = [
+///     1, 1, 0,
+///     1, 1, 0,
+///     0, 2, 2,
+/// ];
+///
+/// let rects = merge_grid_rects(&values, 3, 3, |x| x != 0);
+///
+/// assert_eq!(
+///     rects,
+///     vec![
+///         LdtkGridRect { x: 0, y: 0, width: 2, height: 2 },
+///         LdtkGridRect { x: 1, y: 2, width: 2, height: 1 },
+///     ]
+/// );
+/// ```
+pub fn merge_grid_rects<F: Fn(i32) -> bool>(
+    values: &[i32],
+    width: u32,
+    height: u32,
+    is_solid: F,
+) -> Vec<LdtkGridRect> {
+    let index = |x: u32, y: u32| (y * width + x) as usize;
+
+    // Keep track of which cells have already been added to a rectangle
+    let mut merged = vec![false; values.len()];
+    let is_open = |merged: &[bool], x, y| {
+        matches!(values.get(index(x, y)), Some(&v) if is_solid(v)) && !merged[index(x, y)]
+    };
+
+    let mut rects = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            // Start a new rectangle at every open cell
+            if !is_open(&merged, x, y) {
+                continue;
+            }
+
+            // Grow the rectangle to the right
+            let mut rect_width = 1;
+            while x + rect_width < width && is_open(&merged, x + rect_width, y) {
+                rect_width += 1;
+            }
+
+            // Grow the rectangle down while the whole next row is open
+            let mut rect_height = 1;
+            while y + rect_height < height
+                && (x..x + rect_width).all(|cx| is_open(&merged, cx, y + rect_height))
+            {
+                rect_height += 1;
+            }
+
+            // Mark the cells in the rectangle as merged
+            for ry in y..y + rect_height {
+                for rx in x..x + rect_width {
+                    merged[index(rx, ry)] = true;
+                }
+            }
+
+            rects.push(LdtkGridRect {
+                x,
+                y,
+                width: rect_width,
+                height: rect_height,
+            });
+        }
+    }
+
+    rects
+}
+
+/// The IntGrid values to create colliders for, indexed by layer identifier
+#[derive(Default)]
+pub(crate) struct LdtkColliderRegistry {
+    pub(crate) layers: HashMap<String, Vec<i32>>,
+}
+
+/// Add the LDtk collider registry to the app builder
+pub(crate) fn add_collider_registry(app: &mut AppBuilder) {
+    app.init_resource::<LdtkColliderRegistry>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int_grid(values: Vec<i32>, width: u32, height: u32, scale: f32) -> LdtkIntGrid {
+        LdtkIntGrid {
+            identifier: "Collisions".into(),
+            width,
+            height,
+            grid_size: 16,
+            scale,
+            values,
+            value_identifiers: Default::default(),
+        }
+    }
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> LdtkGridRect {
+        LdtkGridRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn empty_grid_has_no_rects() {
+        assert_eq!(merge_grid_rects(&[0; 6], 3, 2, |x| x != 0), vec![]);
+        assert_eq!(merge_grid_rects(&[], 0, 0, |x| x != 0), vec![]);
+    }
+
+    #[test]
+    fn full_grid_is_one_rect() {
+        assert_eq!(
+            merge_grid_rects(&[1; 12], 4, 3, |x| x != 0),
+            vec![rect(0, 0, 4, 3)]
+        );
+    }
+
+    #[test]
+    fn l_shapes_are_split_greedily() {
+        #[rustfmt::skip]
+        let values = [
+            1, 0, 0,
+            1, 0, 0,
+            1, 1, 1,
+        ];
+        assert_eq!(
+            merge_grid_rects(&values, 3, 3, |x| x != 0),
+            vec![rect(0, 0, 1, 3), rect(1, 2, 2, 1)]
+        );
+
+        #[rustfmt::skip]
+        let values = [
+            1, 1, 1,
+            1, 0, 0,
+            1, 0, 0,
+        ];
+        assert_eq!(
+            merge_grid_rects(&values, 3, 3, |x| x != 0),
+            vec![rect(0, 0, 3, 1), rect(0, 1, 1, 2)]
+        );
+    }
+
+    #[test]
+    fn only_solid_values_are_merged() {
+        #[rustfmt::skip]
+        let values = [
+            1, 2, 1,
+            1, 2, 1,
+        ];
+        assert_eq!(
+            merge_grid_rects(&values, 3, 2, |x| x == 1),
+            vec![rect(0, 0, 1, 2), rect(2, 0, 1, 2)]
+        );
+    }
+
+    #[test]
+    fn missing_values_are_treated_as_empty() {
+        // Fewer values than cells: the missing cells at the end are empty
+        assert_eq!(
+            merge_grid_rects(&[1, 1, 1, 1], 3, 2, |x| x != 0),
+            vec![rect(0, 0, 3, 1), rect(0, 1, 1, 1)]
+        );
+
+        // More values than cells: the extra values are ignored
+        assert_eq!(
+            merge_grid_rects(&[1; 8], 3, 2, |x| x != 0),
+            vec![rect(0, 0, 3, 2)]
+        );
+    }
+
+    #[test]
+    fn colliders_are_in_layer_space() {
+        #[rustfmt::skip]
+        let values = vec![
+            0, 0, 0,
+            0, 1, 1,
+        ];
+        let colliders = LdtkColliders::from_int_grid(&int_grid(values, 3, 2, 2.0), &[1]);
+
+        assert_eq!(colliders.cells, vec![rect(1, 1, 2, 1)]);
+        // Cells are 16px scaled by 2, and go down from the layer origin at its top-left corner.
+        // The layer offset is applied by the transform of the layer entity, not here.
+        assert_eq!(
+            colliders.rects,
+            vec![LdtkColliderRect {
+                center: Vec2::new(64.0, -48.0),
+                half_extents: Vec2::new(32.0, 16.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn colliders_for_unregistered_values_are_empty() {
+        let colliders = LdtkColliders::from_int_grid(&int_grid(vec![2; 4], 2, 2, 1.0), &[1]);
+
+        assert!(colliders.cells.is_empty());
+        assert!(colliders.rects.is_empty());
+    }
+}
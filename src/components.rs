@@ -1,6 +1,6 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-use crate::asset::LdtkMap;
+use crate::{asset::LdtkMap, LdtkYSort, LevelSelection, LevelSelector};
 
 /// A component bundle for spawning an LDtk map
 #[derive(Default, Bundle)]
@@ -16,12 +16,20 @@ pub struct LdtkMapBundle {
 }
 
 /// Configuration for how to display the Ldtk map
+///
+/// Changing the configuration of a map that has already been spawned will re-spawn the map with the
+/// new configuration.
 pub struct LdtkMapConfig {
     /// Whether or not to set the clear color of the screen to match the background color of the
     /// LDtk map.
     pub set_clear_color: bool,
-    /// Which level from the LDtk project to display, if there are more than one level.
-    pub level: usize,
+    /// Which levels from the LDtk project to display.
+    ///
+    /// This defaults to [`LevelSelection::Single`] with the first level, which displays only that
+    /// level. The other selections display several levels at once, each placed at its position in
+    /// the LDtk world. Changing this on a map that has already been spawned will despawn the
+    /// current levels and spawn the newly selected ones.
+    pub levels: LevelSelection,
     /// The scale of the pixels in the tilemap. A scale of 1 means that 1 pixel in the map should
     /// equal 1 pixel on the screen.
     pub scale: f32,
@@ -30,15 +38,128 @@ pub struct LdtkMapConfig {
     /// This default value for this is false, in which case the top-left corner of the map will be
     /// the map origin.
     pub center_map: bool,
+    /// Whether or not to render IntGrid layers that don't have a tileset, using the colors of the
+    /// IntGrid values from the layer definition.
+    ///
+    /// This is useful for debugging and defaults to false.
+    pub render_int_grid: bool,
+    /// How the layers of the map are spread out along the Z axis.
+    ///
+    /// This defaults to [`LayerZStrategy::Step`] with a step of 1, which places each layer one unit
+    /// above the layer below it.
+    pub layer_z: LayerZStrategy,
+    /// The distance along the Z axis between the sublayers that a layer is split into when it has
+    /// more than one tile in the same cell.
+    ///
+    /// This defaults to 0.00001. Maps that are placed far from the origin on the Z axis may need a
+    /// bigger step to keep the sublayers from fighting over which one is on top.
+    pub sublayer_z_step: f32,
+    /// The settings for drawing the tiles of some layers sorted by their Y coordinate.
+    ///
+    /// This defaults to `None`, which doesn't Y-sort anything.
+    pub y_sort: Option<LdtkYSort>,
+    /// The number of cells wide and tall to split the rendered layers into chunks of.
+    ///
+    /// Every chunk is rendered by its own entity, so that editing a tile only re-uploads the tiles
+    /// of its chunk, and chunks that are off screen can be hidden. This defaults to `None`, which
+    /// renders every layer with a single entity.
+    pub chunk_size: Option<u32>,
 }
 
 impl Default for LdtkMapConfig {
     fn default() -> Self {
         LdtkMapConfig {
             set_clear_color: false,
-            level: 0,
+            levels: LevelSelection::Single(LevelSelector::Index(0)),
             scale: 1.0,
             center_map: false,
+            render_int_grid: false,
+            layer_z: LayerZStrategy::Step(1.0),
+            sublayer_z_step: 0.00001,
+            y_sort: None,
+            chunk_size: None,
         }
     }
 }
+
+impl LdtkMapConfig {
+    /// Get the Z coordinate of the layer with the given identifier, relative to its level entity
+    ///
+    /// Returns `None` if the project doesn't have a layer with that identifier.
+    pub fn layer_z(&self, project: &ldtk::Project, identifier: &str) -> Option<f32> {
+        // The layer definitions are in the same order as the layers in every level, with the top
+        // layer first
+        let layer_defs = &project.defs.layers;
+        let position = layer_defs.iter().position(|x| x.identifier == identifier)?;
+
+        Some(self.layer_z.layer_z(
+            identifier,
+            layer_defs.len() - 1 - position,
+            layer_defs.len(),
+        ))
+    }
+}
+
+/// How the layers of a map are spread out along the Z axis
+///
+/// Entities and IntGrid values spawned from a layer are placed at the same Z coordinate as the
+/// layer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerZStrategy {
+    /// Place each layer the given distance above the layer below it, with the bottom layer at 0
+    Step(f32),
+    /// Place the layers with the given identifiers at the given Z coordinates, and every other
+    /// layer at its layer number, with the bottom layer at 0
+    Explicit(HashMap<String, f32>),
+    /// Spread the layers evenly between two Z coordinates, with the bottom layer at `min` and the
+    /// top layer at `max`
+    Range { min: f32, max: f32 },
+}
+
+impl Default for LayerZStrategy {
+    fn default() -> Self {
+        LayerZStrategy::Step(1.0)
+    }
+}
+
+impl LayerZStrategy {
+    /// Get the Z coordinate of a layer, where `index` is the layer number counted from the bottom
+    /// layer and `count` is the number of layers
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy_ldtk::*;
+    ///
+    /// assert_eq!(LayerZStrategy::Step(2.0).layer_z("Walls", 3, 4), 6.0);
+    /// assert_eq!(LayerZStrategy::Range { min: 0.0, max: 1.0 }.layer_z("Walls", 3, 4), 1.0);
+    ///
+    /// let explicit = LayerZStrategy::Explicit(vec![("Walls".into(), 10.0)].into_iter().collect());
+    /// assert_eq!(explicit.layer_z("Walls", 3, 4), 10.0);
+    /// assert_eq!(explicit.layer_z("Floor", 0, 4), 0.0);
+    /// ```
+    pub fn layer_z(&self, identifier: &str, index: usize, count: usize) -> f32 {
+        match self {
+            LayerZStrategy::Step(step) => index as f32 * step,
+            LayerZStrategy::Explicit(z) => z.get(identifier).copied().unwrap_or(index as f32),
+            LayerZStrategy::Range { min, max } => {
+                if count > 1 {
+                    min + (max - min) * index as f32 / (count - 1) as f32
+                } else {
+                    *min
+                }
+            }
+        }
+    }
+}
+
+/// A marker component that is added to a map entity once all of the map's tileset textures have
+/// loaded
+pub struct TilesetsLoaded;
+
+/// A marker component that is added to a map entity once the map has been processed by the
+/// `process_ldtk_maps` system
+///
+/// This is added whether or not the map spawned successfully, and is removed when the map is about
+/// to be re-spawned because its asset or its [`LdtkMapConfig`] changed.
+pub struct LdtkMapHasLoaded;
@@ -0,0 +1,95 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::LdtkColliderRegistry;
+
+/// A component added to every entity spawned from an LDtk entity layer
+pub struct LdtkEntity {
+    /// The entity instance from the LDtk map that this entity was spawned for
+    pub instance: ldtk::EntityInstance,
+}
+
+/// A function that spawns a Bevy entity for an LDtk entity instance
+type EntitySpawner = Box<dyn Fn(&mut Commands, &ldtk::EntityInstance) -> Entity + Send + Sync>;
+
+/// The registry of Bevy bundles to spawn for LDtk entities, indexed by entity identifier
+#[derive(Default)]
+pub(crate) struct LdtkEntityRegistry {
+    spawners: HashMap<String, EntitySpawner>,
+}
+
+impl LdtkEntityRegistry {
+    /// Spawn the registered bundle for the given entity instance, returning `None` if there is no
+    /// bundle registered for the entity's identifier
+    pub(crate) fn spawn(
+        &self,
+        commands: &mut Commands,
+        instance: &ldtk::EntityInstance,
+    ) -> Option<Entity> {
+        self.spawners
+            .get(&instance.__identifier)
+            .map(|spawner| spawner(commands, instance))
+    }
+}
+
+/// Add the LDtk entity registry to the app builder
+pub(crate) fn add_entity_registry(app: &mut AppBuilder) {
+    app.init_resource::<LdtkEntityRegistry>();
+}
+
+/// Extension trait for the [`AppBuilder`] that allows registering bundles to spawn for LDtk
+/// entities and layers to create colliders for
+pub trait LdtkAppBuilderExt {
+    /// Register a bundle to spawn for every LDtk entity with the given identifier
+    ///
+    /// The `constructor` is called with the LDtk entity instance to create the bundle. The spawned
+    /// entity will be made a child of the level entity ([`LdtkMapLevel`]) and will have an
+    /// [`LdtkEntity`] component added to it. Its [`Transform`] will be set to the location of the
+    /// entity in the level, using the same coordinates and [`LdtkMapConfig::scale`] that the map
+    /// layers use, and it will be placed at the Z position of its layer.
+    ///
+    /// [`LdtkMapLevel`]: crate::LdtkMapLevel
+    /// [`LdtkMapConfig::scale`]: crate::LdtkMapConfig::scale
+    fn register_ldtk_entity<B, F>(&mut self, identifier: &str, constructor: F) -> &mut Self
+    where
+        B: Bundle,
+        F: Fn(&ldtk::EntityInstance) -> B + Send + Sync + 'static;
+
+    /// Add an [`LdtkColliders`] component to the IntGrid layers with the given identifier
+    ///
+    /// The cells of the layer that have one of the given `values` will be merged into as few
+    /// rectangles as possible.
+    ///
+    /// [`LdtkColliders`]: crate::LdtkColliders
+    fn register_ldtk_colliders(&mut self, layer_identifier: &str, values: &[i32]) -> &mut Self;
+}
+
+impl LdtkAppBuilderExt for AppBuilder {
+    fn register_ldtk_entity<B, F>(&mut self, identifier: &str, constructor: F) -> &mut Self
+    where
+        B: Bundle,
+        F: Fn(&ldtk::EntityInstance) -> B + Send + Sync + 'static,
+    {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_insert_with(LdtkEntityRegistry::default);
+
+        registry.spawners.insert(
+            identifier.into(),
+            Box::new(move |commands, instance| commands.spawn_bundle(constructor(instance)).id()),
+        );
+
+        self
+    }
+
+    fn register_ldtk_colliders(&mut self, layer_identifier: &str, values: &[i32]) -> &mut Self {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_insert_with(LdtkColliderRegistry::default);
+
+        registry
+            .layers
+            .insert(layer_identifier.into(), values.to_vec());
+
+        self
+    }
+}
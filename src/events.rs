@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use crate::{LdtkTilePick, LevelSelection, LevelSelector};
+
+/// Add the LDtk map events to the app builder
+pub(crate) fn add_events(app: &mut AppBuilder) {
+    app.add_event::<LdtkMapEvent>()
+        .add_event::<LdtkMapError>()
+        .add_event::<LevelSpawned>()
+        .add_event::<LevelDespawned>()
+        .add_event::<LevelEntered>()
+        .add_event::<LevelExited>()
+        .add_event::<LdtkTileHovered>()
+        .add_event::<LdtkTileClicked>();
+}
+
+/// An event that is sent at the different stages of the lifecycle of an LDtk map entity
+///
+/// Every variant carries the map entity and the levels selected by its [`LdtkMapConfig::levels`].
+///
+/// [`LdtkMapConfig::levels`]: crate::LdtkMapConfig::levels
+#[derive(Debug, Clone, PartialEq)]
+pub enum LdtkMapEvent {
+    /// The map asset has loaded and the map is about to be spawned. This is sent again every time
+    /// the map is re-spawned.
+    AssetLoaded { map: Entity, levels: LevelSelection },
+    /// All of the map's tileset textures have loaded
+    TilesetsReady { map: Entity, levels: LevelSelection },
+    /// The map's levels, layers, and entities have been spawned
+    Spawned { map: Entity, levels: LevelSelection },
+    /// The map's levels, layers, and entities have been despawned so that the map can be
+    /// re-spawned, either because the map asset was modified or because the map config changed
+    Despawned { map: Entity, levels: LevelSelection },
+    /// The map asset was modified and the map was updated in place. If the map changed too much to
+    /// be updated in place, it is despawned instead, and a `Despawned` event is sent followed by a
+    /// `Spawned` event once it has been re-spawned.
+    Reloaded { map: Entity, levels: LevelSelection },
+}
+
+impl LdtkMapEvent {
+    /// Get the map entity that the event is for
+    pub fn map(&self) -> Entity {
+        match self {
+            LdtkMapEvent::AssetLoaded { map, .. }
+            | LdtkMapEvent::TilesetsReady { map, .. }
+            | LdtkMapEvent::Spawned { map, .. }
+            | LdtkMapEvent::Despawned { map, .. }
+            | LdtkMapEvent::Reloaded { map, .. } => *map,
+        }
+    }
+
+    /// Get the levels selected by the map's config when the event was sent
+    pub fn levels(&self) -> &LevelSelection {
+        match self {
+            LdtkMapEvent::AssetLoaded { levels, .. }
+            | LdtkMapEvent::TilesetsReady { levels, .. }
+            | LdtkMapEvent::Spawned { levels, .. }
+            | LdtkMapEvent::Despawned { levels, .. }
+            | LdtkMapEvent::Reloaded { levels, .. } => levels,
+        }
+    }
+}
+
+/// An event that is sent when an LDtk map could not be spawned
+///
+/// The map entity is left without any layers, but the rest of the app will keep running. The map
+/// will be spawned again if the map asset is modified.
+#[derive(Debug)]
+pub struct LdtkMapError {
+    /// The map entity that could not be spawned
+    pub entity: Entity,
+    /// The reason the map could not be spawned
+    pub error: LdtkMapSpawnError,
+}
+
+/// An event that is sent when an [`LdtkLevelStreamer`] spawns a level
+///
+/// [`LdtkLevelStreamer`]: crate::LdtkLevelStreamer
+#[derive(Debug, Clone)]
+pub struct LevelSpawned {
+    /// The map entity that the level was spawned for
+    pub map: Entity,
+    /// The spawned level entity
+    pub level: Entity,
+    /// The unique id of the level
+    pub uid: i32,
+    /// The identifier of the level
+    pub identifier: String,
+    /// The iid of the level, if it has one. Levels don't have iids in LDtk 0.8.1.
+    pub iid: Option<String>,
+}
+
+/// An event that is sent when an [`LdtkLevelStreamer`] despawns a level
+///
+/// [`LdtkLevelStreamer`]: crate::LdtkLevelStreamer
+#[derive(Debug, Clone)]
+pub struct LevelDespawned {
+    /// The map entity that the level was despawned from
+    pub map: Entity,
+    /// The unique id of the level
+    pub uid: i32,
+    /// The identifier of the level
+    pub identifier: String,
+    /// The iid of the level, if it has one. Levels don't have iids in LDtk 0.8.1.
+    pub iid: Option<String>,
+}
+
+/// An event that is sent when an entity with an [`LdtkLevelTracker`] enters a spawned level
+///
+/// [`LdtkLevelTracker`]: crate::LdtkLevelTracker
+#[derive(Debug, Clone)]
+pub struct LevelEntered {
+    /// The tracked entity
+    pub entity: Entity,
+    /// The map entity that the level belongs to
+    pub map: Entity,
+    /// The level entity
+    pub level: Entity,
+    /// The unique id of the level
+    pub uid: i32,
+    /// The identifier of the level
+    pub identifier: String,
+    /// The iid of the level, if it has one. Levels don't have iids in LDtk 0.8.1.
+    pub iid: Option<String>,
+}
+
+/// An event that is sent when an entity with an [`LdtkLevelTracker`] leaves a level, either by
+/// moving out of it or because the level was despawned
+///
+/// [`LdtkLevelTracker`]: crate::LdtkLevelTracker
+#[derive(Debug, Clone)]
+pub struct LevelExited {
+    /// The tracked entity
+    pub entity: Entity,
+    /// The map entity that the level belongs to
+    pub map: Entity,
+    /// The unique id of the level
+    pub uid: i32,
+    /// The identifier of the level
+    pub identifier: String,
+    /// The iid of the level, if it has one. Levels don't have iids in LDtk 0.8.1.
+    pub iid: Option<String>,
+}
+
+/// An event that is sent when the tiles under the cursor in the primary window change
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkTileHovered {
+    /// The world position of the cursor
+    pub position: Vec3,
+    /// The tiles under the cursor, ordered from the top-most tile to the bottom-most tile. This is
+    /// empty when the cursor leaves the tiles of the map.
+    pub tiles: Vec<LdtkTilePick>,
+}
+
+/// An event that is sent when a mouse button is pressed while the cursor is over the tiles of a map
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkTileClicked {
+    /// The mouse button that was pressed
+    pub button: MouseButton,
+    /// The world position of the cursor
+    pub position: Vec3,
+    /// The tiles under the cursor, ordered from the top-most tile to the bottom-most tile
+    pub tiles: Vec<LdtkTilePick>,
+}
+
+/// An error that occurs when spawning an LDtk map
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum LdtkMapSpawnError {
+    #[error("The map does not have a level with {0}")]
+    MissingLevel(LevelSelector),
+    #[error("The layers for level `{0}` have not been loaded")]
+    MissingLayers(String),
+    #[error("Could not find tileset `{0}` inside of map data")]
+    MissingTileset(String),
+    #[error("Could not find tileset with uid {0} inside of map data")]
+    MissingTilesetUid(i32),
+    #[error("Could not find layer definition with uid {0} inside of map data")]
+    MissingLayerDefinition(i32),
+    #[error("Invalid color: `{0}`")]
+    InvalidColor(String),
+}
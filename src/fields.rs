@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+use serde_json::Value;
+
+use crate::LdtkEntity;
+
+/// A typed value of an LDtk entity or level field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// A field that doesn't have a value
+    Null,
+    /// An `Int` field
+    Int(i32),
+    /// A `Float` field
+    Float(f32),
+    /// A `Bool` field
+    Bool(bool),
+    /// A `String` or multi-line text field
+    String(String),
+    /// A `Color` field
+    Color(Color),
+    /// A `Point` field, in grid coordinates of the layer that the entity is in
+    Point(IVec2),
+    /// An `Enum` field, containing the identifier of the enum value
+    Enum(String),
+    /// A `FilePath` field, relative to the LDtk project file
+    FilePath(String),
+    /// A reference to another entity
+    EntityRef {
+        /// The unique identifier of the referenced entity
+        entity_iid: String,
+        /// The unique identifier of the layer the referenced entity is in
+        layer_iid: String,
+        /// The unique identifier of the level the referenced entity is in
+        level_iid: String,
+    },
+    /// An array of any of the other field types
+    Array(Vec<FieldValue>),
+}
+
+/// An error that occurs when reading the value of an LDtk field
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum LdtkFieldError {
+    #[error("Could not find field `{0}`")]
+    MissingField(String),
+    #[error("Could not parse value of field `{identifier}` with type `{field_type}`")]
+    InvalidValue {
+        identifier: String,
+        field_type: String,
+    },
+    #[error("Expected field `{identifier}` to be of type `{expected}`, but found {found:?}")]
+    WrongType {
+        identifier: String,
+        expected: &'static str,
+        found: FieldValue,
+    },
+}
+
+impl FieldValue {
+    /// Parse the value of an LDtk field instance based on the field's type, returning `None` if the
+    /// value does not match the type
+    pub fn parse(field_type: &str, value: &Value) -> Option<Self> {
+        // Any type of field may be null
+        if value.is_null() {
+            return Some(FieldValue::Null);
+        }
+
+        // Arrays have their item type inside of angle brackets, such as `Array<Int>`
+        if let Some(item_type) = field_type
+            .strip_prefix("Array<")
+            .and_then(|x| x.strip_suffix('>'))
+        {
+            return value
+                .as_array()?
+                .iter()
+                .map(|item| FieldValue::parse(item_type, item))
+                .collect::<Option<Vec<_>>>()
+                .map(FieldValue::Array);
+        }
+
+        Some(match field_type {
+            "Int" => FieldValue::Int(value.as_i64()? as i32),
+            "Float" => FieldValue::Float(value.as_f64()? as f32),
+            "Bool" => FieldValue::Bool(value.as_bool()?),
+            "String" | "Text" | "Multilines" => FieldValue::String(value.as_str()?.into()),
+            "Color" => FieldValue::Color(Color::hex(value.as_str()?.strip_prefix('#')?).ok()?),
+            "Point" => FieldValue::Point(IVec2::new(
+                value.get("cx")?.as_i64()? as i32,
+                value.get("cy")?.as_i64()? as i32,
+            )),
+            "FilePath" => FieldValue::FilePath(value.as_str()?.into()),
+            "EntityRef" => FieldValue::EntityRef {
+                entity_iid: value.get("entityIid")?.as_str()?.into(),
+                layer_iid: value.get("layerIid")?.as_str()?.into(),
+                level_iid: value.get("levelIid")?.as_str()?.into(),
+            },
+            // Enum types look like `Enum(MyEnum)`, `LocalEnum.MyEnum`, or `ExternEnum.MyEnum`
+            // depending on the LDtk version
+            enum_type
+                if enum_type.starts_with("Enum(")
+                    || enum_type.starts_with("LocalEnum.")
+                    || enum_type.starts_with("ExternEnum.") =>
+            {
+                FieldValue::Enum(value.as_str()?.into())
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Trait for types that can be converted from a [`FieldValue`]
+pub trait FromFieldValue: Sized {
+    /// The name of the type, used in error messages
+    const TYPE_NAME: &'static str;
+
+    /// Convert the field value to this type, returning `None` if the value has the wrong type
+    fn from_field_value(value: &FieldValue) -> Option<Self>;
+}
+
+impl FromFieldValue for FieldValue {
+    const TYPE_NAME: &'static str = "FieldValue";
+
+    fn from_field_value(value: &FieldValue) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+/// Implement [`FromFieldValue`] for a type that is stored in a single [`FieldValue`] variant
+macro_rules! impl_from_field_value {
+    ($type:ty, $name:literal, $($pattern:pat => $value:expr),+) => {
+        impl FromFieldValue for $type {
+            const TYPE_NAME: &'static str = $name;
+
+            fn from_field_value(value: &FieldValue) -> Option<Self> {
+                match value {
+                    $($pattern => Some($value),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_from_field_value!(i32, "Int", FieldValue::Int(x) => *x);
+impl_from_field_value!(f32, "Float", FieldValue::Float(x) => *x, FieldValue::Int(x) => *x as f32);
+impl_from_field_value!(bool, "Bool", FieldValue::Bool(x) => *x);
+impl_from_field_value!(
+    String,
+    "String",
+    FieldValue::String(x) => x.clone(),
+    FieldValue::Enum(x) => x.clone(),
+    FieldValue::FilePath(x) => x.clone()
+);
+impl_from_field_value!(Color, "Color", FieldValue::Color(x) => *x);
+impl_from_field_value!(IVec2, "Point", FieldValue::Point(x) => *x);
+
+impl<T: FromFieldValue> FromFieldValue for Option<T> {
+    const TYPE_NAME: &'static str = "Option";
+
+    fn from_field_value(value: &FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Null => Some(None),
+            value => T::from_field_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromFieldValue> FromFieldValue for Vec<T> {
+    const TYPE_NAME: &'static str = "Array";
+
+    fn from_field_value(value: &FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Array(items) => items.iter().map(T::from_field_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Typed access to the custom fields of LDtk entities and levels
+///
+/// # Example
+///
+/// ```
+/// use bevy_ldtk::*;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let level: ldtk::Level = serde_json::from_value(serde_json::json!({
+/// #     "__neighbours": [], "worldX": 0, "worldY": 0, "bgPivotX": 0.5, "bgPivotY": 0.5,
+/// #     "externalRelPath": null, "bgPos": null, "pxWid": 16, "pxHei": 16, "uid": 0,
+/// #     "identifier": "Level_0", "layerInstances": [], "__bgColor": "#000000",
+/// #     "bgRelPath": null, "bgColor": null, "__bgPos": null,
+/// #     "fieldInstances": [
+/// #         { "__identifier": "Music", "__type": "String", "__value": "cave.ogg",
+/// #           "defUid": 1, "realEditorValues": [] },
+/// #         { "__identifier": "Darkness", "__type": "Float", "__value": 0.5,
+/// #           "defUid": 2, "realEditorValues": [] }
+/// #     ]
+/// # }))?;
+/// let music: String = level.field("Music")?;
+/// let darkness: f32 = level.field("Darkness")?;
+///
+/// assert_eq!(music, "cave.ogg");
+/// assert_eq!(darkness, 0.5);
+/// assert!(level.field::<i32>("Music").is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub trait LdtkFields {
+    /// Get the raw field instances
+    fn field_instances(&self) -> &[ldtk::FieldInstance];
+
+    /// Get the value of the field with the given identifier
+    fn field_value(&self, identifier: &str) -> Result<FieldValue, LdtkFieldError> {
+        let field = self
+            .field_instances()
+            .iter()
+            .find(|x| x.__identifier == identifier)
+            .ok_or_else(|| LdtkFieldError::MissingField(identifier.into()))?;
+
+        FieldValue::parse(&field.__type, &field.__value).ok_or_else(|| {
+            LdtkFieldError::InvalidValue {
+                identifier: identifier.into(),
+                field_type: field.__type.clone(),
+            }
+        })
+    }
+
+    /// Get the value of the field with the given identifier, converted to the type `T`
+    fn field<T: FromFieldValue>(&self, identifier: &str) -> Result<T, LdtkFieldError> {
+        let value = self.field_value(identifier)?;
+
+        T::from_field_value(&value).ok_or_else(|| LdtkFieldError::WrongType {
+            identifier: identifier.into(),
+            expected: T::TYPE_NAME,
+            found: value,
+        })
+    }
+}
+
+impl LdtkFields for ldtk::EntityInstance {
+    fn field_instances(&self) -> &[ldtk::FieldInstance] {
+        &self.field_instances
+    }
+}
+
+impl LdtkFields for ldtk::Level {
+    fn field_instances(&self) -> &[ldtk::FieldInstance] {
+        &self.field_instances
+    }
+}
+
+impl LdtkFields for LdtkEntity {
+    fn field_instances(&self) -> &[ldtk::FieldInstance] {
+        &self.instance.field_instances
+    }
+}
+
+/// Trait for types that can be created from all of the fields of an LDtk entity or level
+///
+/// This is usually implemented with the [`ldtk_fields!`] macro.
+pub trait FromLdtkFields: Sized {
+    /// Create this type from the fields
+    fn from_ldtk_fields<F: LdtkFields + ?Sized>(fields: &F) -> Result<Self, LdtkFieldError>;
+}
+
+/// Define a struct that can be created from the fields of an LDtk entity or level
+///
+/// Each struct field is followed by the identifier of the LDtk field that it is read from.
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_ldtk::*;
+///
+/// ldtk_fields! {
+///     #[derive(Debug)]
+///     pub struct Enemy {
+///         pub health: i32 = "Health",
+///         pub patrol: Vec<IVec2> = "Patrol",
+///         pub loot: Option<String> = "Loot",
+///     }
+/// }
+///
+/// fn main() {
+///     App::build()
+///         .add_plugins(DefaultPlugins)
+///         .add_plugin(LdtkPlugin)
+///         .register_ldtk_entity("Enemy", |instance| {
+///             (Enemy::from_ldtk_fields(instance).expect("Invalid enemy"),)
+///         })
+///         .run();
+/// }
+/// ```
+#[macro_export]
+macro_rules! ldtk_fields {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident: $type:ty = $identifier:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($field_vis $field: $type,)*
+        }
+
+        impl $crate::FromLdtkFields for $name {
+            fn from_ldtk_fields<F: $crate::LdtkFields + ?Sized>(
+                fields: &F,
+            ) -> Result<Self, $crate::LdtkFieldError> {
+                Ok(Self {
+                    $($field: fields.field($identifier)?,)*
+                })
+            }
+        }
+    };
+}
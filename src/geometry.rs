@@ -0,0 +1,297 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{LdtkMap, LdtkMapConfig, LdtkMapLevel, LdtkTilemapLayer};
+
+/// The placement of a map layer in the world, used to convert between LDtk pixel coordinates, layer
+/// cells, and world coordinates
+///
+/// LDtk pixel coordinates are the coordinates used in the LDtk JSON data for tiles and entities,
+/// with (0, 0) at the top-left corner of the layer and +y pointing down. The layer's pixel offset
+/// is applied on top of them, just like the tilemap vertex shader does.
+///
+/// # Example
+///
+/// ```
+/// use bevy::prelude::*;
+/// use bevy_ldtk::*;
+///
+/// let layer = LdtkTilemapLayer {
+///     scale: 2.0,
+///     map_info: LdtkTilemapMapInfo {
+///         width: 10,
+///         height: 5,
+///         grid_size: 16,
+///         center_map: 1,
+///         offset_x: 4,
+///         offset_y: 8,
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// let transform = GlobalTransform::from_xyz(100.0, 50.0, 0.0);
+/// let geometry = LdtkLayerGeometry::from_tilemap_layer(&transform, &layer);
+///
+/// // This is the placement of the top-left vertex of the layer quad in the vertex shader
+/// let scale_factor = layer.scale * layer.map_info.grid_size as f32;
+/// let shader_top_left = Vec3::new(
+///     -0.5 * layer.map_info.width as f32 * scale_factor + 4.0 * layer.scale,
+///     0.5 * layer.map_info.height as f32 * scale_factor - 8.0 * layer.scale,
+///     0.0,
+/// );
+/// assert_eq!(
+///     geometry.ldtk_px_to_world(Vec2::ZERO),
+///     transform.mul_vec3(shader_top_left)
+/// );
+///
+/// // Converting back and forth gives the same position
+/// let world = geometry.ldtk_px_to_world(Vec2::new(40.0, 24.0));
+/// assert_eq!(geometry.world_to_ldtk_px(world), Vec2::new(40.0, 24.0));
+///
+/// // The center of a cell is inside of that cell
+/// assert_eq!(geometry.world_to_cell(geometry.cell_to_world(3, 1)), Some((3, 1)));
+/// assert_eq!(geometry.world_to_cell(Vec3::new(-1000.0, 0.0, 0.0)), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdtkLayerGeometry {
+    /// The global transform that the local positions of the layer are relative to
+    ///
+    /// This is the transform of the level entity for geometry made with
+    /// [`LdtkLayerGeometry::from_layer_instance`] or [`LdtkMapGeometry`], and the transform of the
+    /// rendered layer entity for geometry made with [`LdtkLayerGeometry::from_tilemap_layer`]. They
+    /// are the same unless the layer entity has been moved relative to its level, like the layers
+    /// that have parallax settings. Geometry made with [`LdtkIntGrid::geometry`] is relative to the
+    /// IntGrid layer entity, which is placed at the top-left corner of the layer.
+    ///
+    /// [`LdtkIntGrid::geometry`]: crate::LdtkIntGrid::geometry
+    pub transform: GlobalTransform,
+    /// The scale of the map
+    pub scale: f32,
+    /// The number of pixels wide ( and tall ) a cell in the layer is
+    pub grid_size: u32,
+    /// The number of cells wide the layer is
+    pub width: u32,
+    /// The number of cells tall the layer is
+    pub height: u32,
+    /// The number of cells wide the whole layer is. This is larger than `width` for the rows and
+    /// chunks that a rendered layer is split into, and is used to center them in the same place as
+    /// the rest of the layer.
+    pub layer_width: u32,
+    /// The number of cells tall the whole layer is, like `layer_width`
+    pub layer_height: u32,
+    /// The number of pixels the layer is offset by, with +y being down
+    pub offset: IVec2,
+    /// Whether or not the layer is centered about the level origin
+    pub center_map: bool,
+}
+
+impl LdtkLayerGeometry {
+    /// Get the geometry of an LDtk layer, where `transform` is the global transform of the level
+    /// entity that the layer was spawned under
+    pub fn from_layer_instance(
+        transform: &GlobalTransform,
+        layer: &ldtk::LayerInstance,
+        config: &LdtkMapConfig,
+    ) -> Self {
+        LdtkLayerGeometry {
+            transform: *transform,
+            scale: config.scale,
+            grid_size: layer.__grid_size as u32,
+            width: layer.__c_wid as u32,
+            height: layer.__c_hei as u32,
+            layer_width: layer.__c_wid as u32,
+            layer_height: layer.__c_hei as u32,
+            offset: IVec2::new(layer.__px_total_offset_x, layer.__px_total_offset_y),
+            center_map: config.center_map,
+        }
+    }
+
+    /// Get the geometry of a rendered layer from its render resources, where `transform` is the
+    /// global transform of the layer entity
+    pub fn from_tilemap_layer(transform: &GlobalTransform, layer: &LdtkTilemapLayer) -> Self {
+        let map_info = &layer.map_info;
+        let layer_size = map_info.layer_size();
+        LdtkLayerGeometry {
+            transform: *transform,
+            scale: layer.scale,
+            grid_size: map_info.grid_size,
+            width: map_info.width,
+            height: map_info.height,
+            layer_width: layer_size.x,
+            layer_height: layer_size.y,
+            offset: IVec2::new(map_info.offset_x, map_info.offset_y),
+            center_map: map_info.center_map != 0,
+        }
+    }
+
+    /// Get the size of the layer in world units, before `transform` is applied
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * self.grid_size as f32 * self.scale
+    }
+
+    /// Get the size of the whole layer in world units, before `transform` is applied
+    pub fn layer_size(&self) -> Vec2 {
+        Vec2::new(self.layer_width as f32, self.layer_height as f32)
+            * self.grid_size as f32
+            * self.scale
+    }
+
+    /// Convert an LDtk pixel position to a local position, relative to `transform`
+    pub fn ldtk_px_to_local(&self, px: Vec2) -> Vec2 {
+        // LDtk's +y is down, so we flip the y axis
+        let mut local =
+            Vec2::new(px.x + self.offset.x as f32, -(px.y + self.offset.y as f32)) * self.scale;
+
+        // If the map is centered, offset the position by half of the size of the whole layer
+        if self.center_map {
+            let size = self.layer_size();
+            local += Vec2::new(-size.x, size.y) / 2.0;
+        }
+
+        local
+    }
+
+    /// Convert a local position, relative to `transform`, to an LDtk pixel position
+    pub fn local_to_ldtk_px(&self, local: Vec2) -> Vec2 {
+        let mut local = local;
+        if self.center_map {
+            let size = self.layer_size();
+            local -= Vec2::new(-size.x, size.y) / 2.0;
+        }
+
+        Vec2::new(
+            local.x / self.scale - self.offset.x as f32,
+            -local.y / self.scale - self.offset.y as f32,
+        )
+    }
+
+    /// Convert an LDtk pixel position to a world position
+    pub fn ldtk_px_to_world(&self, px: Vec2) -> Vec3 {
+        self.transform
+            .mul_vec3(self.ldtk_px_to_local(px).extend(0.0))
+    }
+
+    /// Convert a world position to an LDtk pixel position
+    pub fn world_to_ldtk_px(&self, world: Vec3) -> Vec2 {
+        let local = self
+            .transform
+            .compute_matrix()
+            .inverse()
+            .transform_point3(world);
+        self.local_to_ldtk_px(local.truncate())
+    }
+
+    /// Get the coordinates of the cell at a world position, with (0, 0) being the top-left cell
+    ///
+    /// Returns `None` if the position is outside of the layer.
+    pub fn world_to_cell(&self, world: Vec3) -> Option<(u32, u32)> {
+        let cell = (self.world_to_ldtk_px(world) / self.grid_size as f32).floor();
+
+        if cell.x < 0.0
+            || cell.y < 0.0
+            || cell.x >= self.width as f32
+            || cell.y >= self.height as f32
+        {
+            return None;
+        }
+
+        Some((cell.x as u32, cell.y as u32))
+    }
+
+    /// Get the world position of the center of a cell
+    pub fn cell_to_world(&self, x: u32, y: u32) -> Vec3 {
+        let grid_size = self.grid_size as f32;
+        self.ldtk_px_to_world((Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * grid_size)
+    }
+}
+
+/// A system parameter used to get the [`LdtkLayerGeometry`] of the layers of spawned maps
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_ldtk::*;
+///
+/// struct Player;
+///
+/// fn print_player_cell(
+///     geometry: LdtkMapGeometry,
+///     maps: Query<Entity, With<LdtkMapConfig>>,
+///     players: Query<&Transform, With<Player>>,
+/// ) {
+///     for map in maps.iter() {
+///         if let Some(ground) = geometry.layer(map, "Ground") {
+///             for transform in players.iter() {
+///                 println!("{:?}", ground.world_to_cell(transform.translation));
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct LdtkMapGeometry<'a> {
+    maps: Query<
+        'a,
+        (
+            &'static Handle<LdtkMap>,
+            &'static LdtkMapConfig,
+            &'static GlobalTransform,
+        ),
+    >,
+    levels: Query<
+        'a,
+        (
+            Entity,
+            &'static LdtkMapLevel,
+            &'static GlobalTransform,
+            &'static Parent,
+        ),
+    >,
+    map_assets: Res<'a, Assets<LdtkMap>>,
+}
+
+impl<'a> LdtkMapGeometry<'a> {
+    /// Get the geometry of the layer with the given identifier in the first spawned level of a map
+    ///
+    /// This is meant for maps that only display a single level. Use
+    /// [`LdtkMapGeometry::level_layer`] to choose the level for maps with several levels.
+    pub fn layer(&self, map: Entity, identifier: &str) -> Option<LdtkLayerGeometry> {
+        self.levels
+            .iter()
+            .filter(|(_, _, _, parent)| parent.0 == map)
+            .min_by_key(|(_, level, _, _)| level.index)
+            .and_then(|(level_ent, ..)| self.level_layer(level_ent, identifier))
+    }
+
+    /// Get the geometry of the layer with the given identifier in a spawned level, where `level`
+    /// is the entity with the [`LdtkMapLevel`] component
+    pub fn level_layer(&self, level: Entity, identifier: &str) -> Option<LdtkLayerGeometry> {
+        let (_, map_level, transform, parent) = self.levels.get(level).ok()?;
+        let (map_handle, config, _) = self.maps.get(parent.0).ok()?;
+        let map = self.map_assets.get(map_handle)?;
+
+        let layer = map
+            .project
+            .levels
+            .get(map_level.index)?
+            .layer_instances
+            .as_ref()?
+            .iter()
+            .find(|x| x.__identifier == identifier)?;
+
+        Some(LdtkLayerGeometry::from_layer_instance(
+            transform, layer, config,
+        ))
+    }
+
+    /// Get the world Z coordinate of the layer with the given identifier in a map
+    ///
+    /// This can be used to place sprites between two layers of the map.
+    pub fn layer_z(&self, map: Entity, identifier: &str) -> Option<f32> {
+        let (map_handle, config, transform) = self.maps.get(map).ok()?;
+        let map = self.map_assets.get(map_handle)?;
+        let z = config.layer_z(&map.project, identifier)?;
+
+        Some(transform.mul_vec3(Vec3::new(0.0, 0.0, z)).z)
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::LdtkLayerGeometry;
+
+/// A component containing the values of an LDtk IntGrid layer
+///
+/// An entity with this component is spawned as a child of the level entity ([`LdtkMapLevel`]) for
+/// every IntGrid layer in the level. The entity's [`Transform`] is placed at the top-left corner of
+/// the layer, so that world positions can be converted to grid cells using the entity's
+/// [`GlobalTransform`].
+///
+/// [`LdtkMapLevel`]: crate::LdtkMapLevel
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkIntGrid {
+    /// The identifier of the layer
+    pub identifier: String,
+    /// The number of cells wide the layer is
+    pub width: u32,
+    /// The number of cells tall the layer is
+    pub height: u32,
+    /// The number of pixels wide ( and tall ) a cell in the layer is
+    pub grid_size: u32,
+    /// The scale of the map that the layer was spawned with
+    pub scale: f32,
+    /// The value of every cell in the layer, stored from left to right, and top to bottom. A value
+    /// of `0` means that the cell is empty.
+    pub values: Vec<i32>,
+    /// A mapping of IntGrid values to their identifiers from the layer definition
+    pub value_identifiers: HashMap<i32, String>,
+}
+
+impl LdtkIntGrid {
+    /// Create an IntGrid component from an LDtk layer and its definition
+    pub(crate) fn new(layer: &ldtk::LayerInstance, layer_def: &ldtk::LayerDef, scale: f32) -> Self {
+        let width = layer.__c_wid as u32;
+        let height = layer.__c_hei as u32;
+
+        // Use the CSV values if present, otherwise fall back to the deprecated sparse format
+        let values = if !layer.int_grid_csv.is_empty() {
+            layer.int_grid_csv.clone()
+        } else {
+            let mut values = vec![0; (width * height) as usize];
+            for value in layer.int_grid.iter().flatten() {
+                if let Some(cell) = values.get_mut(value.coord_id as usize) {
+                    *cell = value.v;
+                }
+            }
+            values
+        };
+
+        LdtkIntGrid {
+            identifier: layer.__identifier.clone(),
+            width,
+            height,
+            grid_size: layer.__grid_size as u32,
+            scale,
+            values,
+            value_identifiers: layer_def
+                .int_grid_values
+                .iter()
+                .filter_map(|x| x.identifier.clone().map(|identifier| (x.value, identifier)))
+                .collect(),
+        }
+    }
+
+    /// Get the value of the cell at the given coordinates, with (0, 0) being the top-left cell
+    ///
+    /// Returns `None` if the coordinates are outside of the layer.
+    pub fn get(&self, x: u32, y: u32) -> Option<i32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.values.get((y * self.width + x) as usize).copied()
+    }
+
+    /// Get the identifier of the value of the cell at the given coordinates, if the value has an
+    /// identifier
+    pub fn get_identifier(&self, x: u32, y: u32) -> Option<&str> {
+        self.get(x, y)
+            .and_then(|value| self.value_identifier(value))
+    }
+
+    /// Get the identifier for an IntGrid value from the layer definition
+    pub fn value_identifier(&self, value: i32) -> Option<&str> {
+        self.value_identifiers.get(&value).map(String::as_str)
+    }
+
+    /// Get the geometry of the layer, where `transform` is the [`GlobalTransform`] of this layer's
+    /// entity
+    ///
+    /// The layer entity is placed at the top-left corner of the layer, so the geometry has no
+    /// offset and isn't centered.
+    pub fn geometry(&self, transform: &GlobalTransform) -> LdtkLayerGeometry {
+        LdtkLayerGeometry {
+            transform: *transform,
+            scale: self.scale,
+            grid_size: self.grid_size,
+            width: self.width,
+            height: self.height,
+            layer_width: self.width,
+            layer_height: self.height,
+            offset: IVec2::ZERO,
+            center_map: false,
+        }
+    }
+
+    /// Get the coordinates of the cell at the given world position, where `transform` is the
+    /// [`GlobalTransform`] of this layer's entity
+    ///
+    /// Returns `None` if the position is outside of the layer.
+    pub fn world_to_cell(&self, transform: &GlobalTransform, position: Vec3) -> Option<(u32, u32)> {
+        self.geometry(transform).world_to_cell(position)
+    }
+
+    /// Get the value of the cell at the given world position, where `transform` is the
+    /// [`GlobalTransform`] of this layer's entity
+    pub fn get_at_world(&self, transform: &GlobalTransform, position: Vec3) -> Option<i32> {
+        self.world_to_cell(transform, position)
+            .and_then(|(x, y)| self.get(x, y))
+    }
+}
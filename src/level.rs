@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+
+use crate::{LdtkMap, LdtkMapConfig, LdtkMapSpawnError};
+
+/// A component added to the entity spawned for each level of a map
+///
+/// The level entities are spawned as children of the map entity, and the map layers are spawned as
+/// children of the level entities.
+#[derive(Debug, Clone)]
+pub struct LdtkMapLevel {
+    /// The index of the level in the LDtk project
+    pub index: usize,
+    /// The unique id of the level
+    pub uid: i32,
+    /// The identifier of the level
+    pub identifier: String,
+    /// The iid of the level, if it has one. Levels don't have iids in LDtk 0.8.1.
+    pub iid: Option<String>,
+    /// The area covered by the level, relative to the map entity
+    pub bounds: LdtkLevelBounds,
+}
+
+/// A rectangle covering the area of a level, in world units relative to the map entity
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdtkLevelBounds {
+    /// The bottom-left corner of the level
+    pub min: Vec2,
+    /// The top-right corner of the level
+    pub max: Vec2,
+}
+
+impl LdtkLevelBounds {
+    /// Get whether or not the point is inside of the level
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmplt(self.max).all()
+    }
+
+    /// Get the distance from the point to the closest edge of the level, or zero if the point is
+    /// inside of the level
+    pub fn distance(&self, point: Vec2) -> f32 {
+        (point.max(self.min).min(self.max) - point).length()
+    }
+}
+
+/// A marker component for entities that should send [`LevelEntered`] and [`LevelExited`] events
+/// when they move between the spawned levels of a map
+///
+/// [`LevelEntered`]: crate::LevelEntered
+/// [`LevelExited`]: crate::LevelExited
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LdtkLevelTracker;
+
+/// A component that streams in the levels of a map around the entity that it is added to, such as
+/// the player or the camera
+///
+/// The streamer only works with maps that have [`LdtkMapConfig::levels`] set to
+/// [`LevelSelection::Streamed`]. The level that the streamer is in is spawned, along with all of
+/// the levels that can be reached from it through the level's neighbours while staying within
+/// `radius` of the streamer. Any other levels are despawned. If the streamer isn't inside any
+/// level, the levels within `radius` of it are spawned instead.
+///
+/// A [`LevelSpawned`] or [`LevelDespawned`] event is sent for every level that the streamer spawns
+/// or despawns.
+///
+/// [`LdtkMapConfig::levels`]: crate::LdtkMapConfig::levels
+/// [`LevelSpawned`]: crate::LevelSpawned
+/// [`LevelDespawned`]: crate::LevelDespawned
+#[derive(Debug, Clone)]
+pub struct LdtkLevelStreamer {
+    /// The map entity to stream the levels of
+    pub map: Entity,
+    /// The distance from the streamer, in world units, that neighbouring levels will be spawned
+    /// within
+    pub radius: f32,
+}
+
+impl LdtkLevelStreamer {
+    /// Create a level streamer for the given map entity
+    pub fn new(map: Entity, radius: f32) -> Self {
+        LdtkLevelStreamer { map, radius }
+    }
+}
+
+/// Selects a single level of an LDtk project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelSelector {
+    /// The level at the given index in the project
+    Index(usize),
+    /// The level with the given identifier, such as `Level_0`
+    Identifier(String),
+    /// The level with the given unique id
+    Uid(i32),
+    /// The level with the given iid, for projects saved with a version of LDtk that gives levels
+    /// iids
+    Iid(String),
+}
+
+impl Default for LevelSelector {
+    fn default() -> Self {
+        LevelSelector::Index(0)
+    }
+}
+
+impl From<usize> for LevelSelector {
+    fn from(index: usize) -> Self {
+        LevelSelector::Index(index)
+    }
+}
+
+impl From<&str> for LevelSelector {
+    fn from(identifier: &str) -> Self {
+        LevelSelector::Identifier(identifier.into())
+    }
+}
+
+impl From<String> for LevelSelector {
+    fn from(identifier: String) -> Self {
+        LevelSelector::Identifier(identifier)
+    }
+}
+
+impl std::fmt::Display for LevelSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelSelector::Index(index) => write!(f, "index {}", index),
+            LevelSelector::Identifier(identifier) => write!(f, "identifier `{}`", identifier),
+            LevelSelector::Uid(uid) => write!(f, "uid {}", uid),
+            LevelSelector::Iid(iid) => write!(f, "iid `{}`", iid),
+        }
+    }
+}
+
+impl LevelSelector {
+    /// Get the index of the selected level in the project
+    pub fn resolve(&self, map: &LdtkMap) -> Result<usize, LdtkMapSpawnError> {
+        let levels = &map.project.levels;
+
+        match self {
+            LevelSelector::Index(index) => Some(*index).filter(|&i| i < levels.len()),
+            LevelSelector::Identifier(identifier) => {
+                levels.iter().position(|x| &x.identifier == identifier)
+            }
+            LevelSelector::Uid(uid) => levels.iter().position(|x| x.uid == *uid),
+            LevelSelector::Iid(iid) => levels
+                .iter()
+                .position(|x| map.level_iids.get(&x.uid) == Some(iid)),
+        }
+        .ok_or_else(|| LdtkMapSpawnError::MissingLevel(self.clone()))
+    }
+}
+
+/// Which levels of an LDtk project to spawn
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelSelection {
+    /// Spawn only the selected level, at the map origin
+    Single(LevelSelector),
+    /// Spawn all of the levels in the project, laid out according to the world layout
+    All,
+    /// Spawn the levels with the given indexes, laid out according to the world layout
+    Indices(Vec<usize>),
+    /// Spawn the levels with the given identifiers, laid out according to the world layout
+    Identifiers(Vec<String>),
+    /// Spawn the levels with the given unique ids, laid out according to the world layout
+    Uids(Vec<i32>),
+    /// Spawn the levels with the given iids, laid out according to the world layout
+    Iids(Vec<String>),
+    /// Don't spawn any levels with the map, and let [`LdtkLevelStreamer`]s spawn the levels around
+    /// them instead, laid out according to the world layout
+    Streamed,
+}
+
+impl Default for LevelSelection {
+    fn default() -> Self {
+        LevelSelection::Single(LevelSelector::default())
+    }
+}
+
+impl LevelSelection {
+    /// Get the indexes of the selected levels in the project
+    pub fn select(&self, map: &LdtkMap) -> Result<Vec<usize>, LdtkMapSpawnError> {
+        let resolve_all = |selectors: Vec<LevelSelector>| {
+            selectors
+                .iter()
+                .map(|x| x.resolve(map))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        match self {
+            LevelSelection::Single(level) => Ok(vec![level.resolve(map)?]),
+            LevelSelection::All => Ok((0..map.project.levels.len()).collect()),
+            LevelSelection::Indices(indices) => {
+                resolve_all(indices.iter().copied().map(LevelSelector::Index).collect())
+            }
+            LevelSelection::Identifiers(identifiers) => resolve_all(
+                identifiers
+                    .iter()
+                    .cloned()
+                    .map(LevelSelector::Identifier)
+                    .collect(),
+            ),
+            LevelSelection::Uids(uids) => {
+                resolve_all(uids.iter().copied().map(LevelSelector::Uid).collect())
+            }
+            LevelSelection::Iids(iids) => {
+                resolve_all(iids.iter().cloned().map(LevelSelector::Iid).collect())
+            }
+            LevelSelection::Streamed => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Get the position of the top-left corner of a level in the world, in LDtk pixels
+///
+/// For the `LinearHorizontal` and `LinearVertical` world layouts, the levels are placed one after
+/// another in the order that they are in the project. For the other layouts, the level's world
+/// coordinates are used.
+pub fn level_world_position(project: &ldtk::Project, level_index: usize) -> IVec2 {
+    let previous_levels = project.levels.iter().take(level_index);
+
+    match project.world_layout.as_str() {
+        Some("LinearHorizontal") => IVec2::new(previous_levels.map(|x| x.px_wid).sum(), 0),
+        Some("LinearVertical") => IVec2::new(0, previous_levels.map(|x| x.px_hei).sum()),
+        _ => {
+            let level = &project.levels[level_index];
+            IVec2::new(level.world_x, level.world_y)
+        }
+    }
+}
+
+/// Get the position of the top-left corner of a level relative to the map entity, in world units
+///
+/// A level is placed at the map origin when only a single level is displayed, otherwise it is
+/// placed at its position in the LDtk world.
+pub(crate) fn level_origin(
+    project: &ldtk::Project,
+    level_index: usize,
+    config: &LdtkMapConfig,
+) -> Vec2 {
+    if let LevelSelection::Single(_) = config.levels {
+        Vec2::ZERO
+    } else {
+        // LDtk's +y is down, so we flip the y axis
+        let world_position = level_world_position(project, level_index);
+        Vec2::new(world_position.x as f32, -world_position.y as f32) * config.scale
+    }
+}
+
+/// Get the area covered by a level, relative to the map entity
+pub(crate) fn level_bounds(
+    project: &ldtk::Project,
+    level_index: usize,
+    config: &LdtkMapConfig,
+) -> LdtkLevelBounds {
+    let level = &project.levels[level_index];
+    let size = Vec2::new(level.px_wid as f32, level.px_hei as f32) * config.scale;
+
+    // If the map is centered, the level is centered about its origin
+    let mut top_left = level_origin(project, level_index, config);
+    if config.center_map {
+        top_left += Vec2::new(-size.x, size.y) / 2.0;
+    }
+
+    LdtkLevelBounds {
+        min: Vec2::new(top_left.x, top_left.y - size.y),
+        max: Vec2::new(top_left.x + size.x, top_left.y),
+    }
+}
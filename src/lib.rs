@@ -23,7 +23,7 @@
 //! ```no_run
 //! use bevy::prelude::*;
 //! use bevy_ldtk::*;
-//! 
+//!
 //! fn main() {
 //!     App::build()
 //!         .add_plugins(DefaultPlugins)
@@ -31,11 +31,11 @@
 //!         .add_startup_system(setup.system())
 //!         .run();
 //! }
-//! 
+//!
 //! fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 //!     // Enable hot reload
 //!     asset_server.watch_for_changes().unwrap();
-//! 
+//!
 //!     commands
 //!         // Spawn the map
 //!         .spawn()
@@ -44,15 +44,17 @@
 //!             config: LdtkMapConfig {
 //!                 set_clear_color: true,
 //!                 scale: 1.0,
-//!                 level: std::env::args()
-//!                     .nth(2)
-//!                     .map(|x| x.parse().unwrap())
-//!                     .unwrap_or(0),
 //!                 center_map: false,
+//!                 render_int_grid: false,
+//!                 levels: LevelSelection::Single(LevelSelector::Index(0)),
+//!                 layer_z: LayerZStrategy::Step(1.0),
+//!                 sublayer_z_step: 0.00001,
+//!                 y_sort: None,
+//!                 chunk_size: None,
 //!             },
 //!             ..Default::default()
 //!         });
-//! 
+//!
 //!     // And the camera
 //!     commands
 //!         .spawn()
@@ -68,6 +70,31 @@
 //! map, their Z axis translation must be higher than the map transform + the layer number that you
 //! want it to appear above.
 //!
+//! The spacing between the layers can be changed with `LdtkMapConfig::layer_z`, which can also
+//! place layers at explicit Z coordinates by identifier or spread them across a range. To place a
+//! sprite between two layers, the `LdtkMapGeometry::layer_z` method gives the world Z coordinate
+//! of a layer by its identifier.
+//!
+//! ## Levels
+//!
+//! Each spawned level gets its own entity with an `LdtkMapLevel` component, which is a child of the
+//! map entity, and the level's layers are spawned as children of the level entity. By default
+//! `LdtkMapConfig::levels` is `LevelSelection::Single`, which spawns only the first level, at the
+//! map origin. The level can be selected by its index, identifier, unique id, or iid using a
+//! `LevelSelector`, and changing it on a map that has already been spawned will despawn the current
+//! level and spawn the new one. To spawn several levels at once, use a different `LevelSelection`,
+//! and each level will be placed at its position in the LDtk world layout.
+//!
+//! For worlds that are too big to spawn at once, set `LdtkMapConfig::levels` to
+//! `LevelSelection::Streamed` and add an `LdtkLevelStreamer` component to the player or the
+//! camera. The level that the streamer is in and its neighbours within the streamer's radius will
+//! be spawned, and levels that fall out of range will be despawned, sending `LevelSpawned` and
+//! `LevelDespawned` events.
+//!
+//! Entities with an `LdtkLevelTracker` component are tracked against the bounds of the spawned
+//! levels, and `LevelEntered` and `LevelExited` events are sent when they move from one level to
+//! another.
+//!
 //! ## LDtk Versions
 //!
 //! | LDtk Version | Plugin Version |
@@ -86,89 +113,242 @@
 //! # Features
 //!
 //! - An efficient renderer that only uses 4 vertices per map layer and lays out tiles on the GPU
-//! - Supports hot reload through the Bevy asset server integration
+//! - Supports hot reload through the Bevy asset server integration, only updating the layers that
+//!   changed and keeping the entities spawned from the map
 //! - Heavily commented code to help others who want to see how to make their own tilemap renderers.
 //!
 //! # Caveats
 //!
 //! The plugin is in relatively early stages, but it is still rather functional for many basic maps
 //!
-//! - Many features are not supported yet, including:
-//!   - tilesets with spacing in them
-//!   - levels in separate files
 //! - Occasionally some slight rendering artifacts between tiles. ( [#1] ) Not sure what causes
 //!   those yet. Help from anybody with rendering experience would be greatly appreciated!
 //!
+//! ## IntGrid Layers
+//!
+//! Every IntGrid layer in a level is spawned as a child of the level entity (`LdtkMapLevel`) with
+//! an `LdtkIntGrid` component. The component holds the value of every cell in the layer, and can be
+//! used to look up the value at a cell or at a world position, which is useful for things like
+//! collision.
+//!
+//! You can also have the solid cells of an IntGrid layer merged into collision rectangles by
+//! registering the layer with `register_ldtk_colliders`. An `LdtkColliders` component with the
+//! rectangles will be added to the layer's entity.
+//!
+//! IntGrid layers that don't have a tileset are not rendered by default, but you can set
+//! `LdtkMapConfig::render_int_grid` to render them using the colors of their IntGrid values.
+//!
+//! ## Spawning Entities
+//!
+//! You can register a bundle to be spawned for each entity in the map's entity layers with a given
+//! identifier. The spawned entities are made children of the level entity (`LdtkMapLevel`), are
+//! positioned at their location in the level, and have an `LdtkEntity` component with the entity
+//! data from the map.
+//!
+//! The custom fields of LDtk entities and levels can be read as typed values with the `LdtkFields`
+//! trait, and whole structs can be created from an entity's fields with the `ldtk_fields!` macro.
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_ldtk::*;
+//! struct Player;
+//!
+//! fn main() {
+//!     App::build()
+//!         .add_plugins(DefaultPlugins)
+//!         .add_plugin(LdtkPlugin)
+//!         .register_ldtk_entity("Player_Spawn", |_| (Player,))
+//!         .run();
+//! }
+//! ```
+//!
+//! ## Editing Tiles
+//!
+//! Every rendered layer entity has an `LdtkTileLayer` component with the identifier of its LDtk
+//! layer. The tiles of a spawned layer can be changed at runtime with the `LdtkTileLayers` system
+//! parameter, which takes care of the order that the tiles are stored in and of layers that are
+//! split into several sublayers:
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_ldtk::*;
+//! fn open_door(mut tile_layers: LdtkTileLayers, levels: Query<Entity, With<LdtkMapLevel>>) {
+//!     for level in levels.iter() {
+//!         if let Some(mut doors) = tile_layers.layer_mut(level, "Doors") {
+//!             doors.clear_tile(10, 4);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! The opacity, tint, and visibility of a layer are render resources in its `LdtkTilemapLayer`,
+//! taken from the layer's opacity and visibility in LDtk when the layer is spawned. They can be
+//! changed the same way, for example to fade out a foreground layer while the player is behind it,
+//! with `set_opacity`, `set_tint`, and `set_visible`.
+//!
+//! ## Coordinates
+//!
+//! LDtk positions are in pixels, with (0, 0) at the top-left corner of the layer and +y pointing
+//! down. The `LdtkMapGeometry` system parameter gives you an `LdtkLayerGeometry` for a layer of a
+//! spawned map, which converts between LDtk pixels, layer cells, and world positions, taking into
+//! account the map transform and the `scale` and `center_map` settings of the `LdtkMapConfig`.
+//!
+//! ## Picking Tiles
+//!
+//! The `LdtkTilePicker` system parameter finds the tiles of the spawned layers under a world
+//! position, giving you the layer identifier, the cell, the tileset tile id, the flip bits, and the
+//! tile's custom data. When the app has windows and mouse input, the plugin also picks the tiles
+//! under the cursor of the primary window, as seen by the 2D camera, and sends `LdtkTileHovered`
+//! and `LdtkTileClicked` events:
+//!
+//! ```no_run
+//! # use bevy::prelude::*;
+//! # use bevy_ldtk::*;
+//! fn print_clicked_tiles(mut clicked_events: EventReader<LdtkTileClicked>) {
+//!     for event in clicked_events.iter() {
+//!         for tile in &event.tiles {
+//!             println!("Clicked tile {} in layer {}", tile.tile_id, tile.identifier);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! The custom data and enum tags of the tiles of every tileset are kept in
+//! `LdtkMap::tileset_meta`, as an `LdtkTilesetMeta` for each tileset. The
+//! `LdtkTilePicker::tile_enum_tags` method gets the enum tags of the tiles in a cell of a layer,
+//! which is handy for terrain types like ice or spikes.
+//!
+//! ## Animated Tiles
+//!
+//! LDtk doesn't support animated tiles, but tiles can be animated by describing the animation in
+//! the tile's custom data in the tileset, listing the tile ids of the frames and how many seconds
+//! each frame is displayed for:
+//!
+//! ```json
+//! { "animation": { "frames": [12, 13, 14], "durations": [0.1, 0.1, 0.2] } }
+//! ```
+//!
+//! Every tile placed in the map with that tile id will play the animation. The animations can be
+//! sped up, slowed down, or paused for a map by adding an `LdtkTileAnimationConfig` component to
+//! the map entity.
+//!
+//! ## Parallax
+//!
+//! Layers with a parallax factor in LDtk are moved relative to the active 2D camera to create a
+//! fake 3D effect, and scaled too if parallax scaling is enabled for the layer. The factors are
+//! taken from the layer definition, and can be overridden for a rendered layer by adding an
+//! `LdtkParallax` component to its entity.
+//!
+//! ## Y-Sorting
+//!
+//! Top-down games often need characters to walk both in front of and behind things like trees and
+//! walls. Setting `LdtkMapConfig::y_sort` to an `LdtkYSort` picks out the layers, by identifier, or
+//! the tiles, by the enum values they are tagged with in the tileset, that should be sorted by
+//! their Y coordinate. Those tiles are rendered in separate rows, each placed on the Z axis
+//! according to the Y coordinate of its bottom edge, so that lower rows are drawn on top.
+//!
+//! Adding an `LdtkYSorted` component to a sprite places it on the Z axis the same way, so it is
+//! drawn in between the rows. Entities spawned from Y-sorted layers get the component
+//! automatically.
+//!
+//! ## Chunks
+//!
+//! Every sublayer is normally rendered by a single entity, with one buffer holding all of its
+//! tiles. For very large levels, set `LdtkMapConfig::chunk_size` to split the layers into square
+//! chunks of that many cells, each rendered by its own entity with its own `LdtkTilemapLayer`.
+//! Editing a tile then only re-uploads the tiles of its chunk, and chunks that are off screen can
+//! be hidden through their `Visible` component. The `chunk_origin` of the `LdtkTileLayer` component
+//! gives the cell that a chunk starts at, and `LdtkTileLayers` and `LdtkTilePicker` work across the
+//! chunks of a layer the same way they do for whole layers.
+//!
 //! ## Extracting Map Information
 //!
-//! You can extract any information necessary for your game from the LDtk JSON map data. Here is an example showing how you could spawn a player.
+//! You can extract any information necessary for your game from the LDtk JSON map data. Here is an example showing how you could spawn a player once the first level of the map has been spawned, using `LdtkMapGeometry` to place it in the world.
 //!
 //! ```no_run
 //! # use bevy::prelude::*;
 //! # use bevy_ldtk::*;
+//! /// A marker for the player sprite
+//! struct Player;
+//!
 //! fn spawn_player(
 //!     mut commands: Commands,
-//!     printed_maps: Local<Vec<Entity>>,
-//!     query: Query<(Entity, &Handle<LdtkMap>)>,
+//!     players: Query<(), With<Player>>,
+//!     maps: Query<(Entity, &Handle<LdtkMap>)>,
+//!     levels: Query<(Entity, &LdtkMapLevel, &Parent)>,
 //!     map_assets: Res<Assets<LdtkMap>>,
+//!     map_geometry: LdtkMapGeometry,
 //!     asset_server: Res<AssetServer>,
 //!     mut color_materials: ResMut<Assets<ColorMaterial>>,
 //! ) {
-//!     for (ent, handle) in query.iter() {
-//!         // Skip any maps we have already printed the spawn location for
-//!         if printed_maps.contains(&ent) {
-//!             continue;
-//!         }
+//!     // Only spawn the player once, even if the map is re-spawned
+//!     if players.iter().next().is_some() {
+//!         return;
+//!     }
+//!
+//!     for (map_ent, map_handle) in maps.iter() {
+//!         // Wait for the map asset to load
+//!         let map = match map_assets.get(map_handle) {
+//!             Some(map) => map,
+//!             None => continue,
+//!         };
+//!
+//!         // Wait for the first level of the map to be spawned
+//!         let (level_ent, map_level) = match levels
+//!             .iter()
+//!             .filter(|(_, _, parent)| parent.0 == map_ent)
+//!             .min_by_key(|(_, level, _)| level.index)
+//!         {
+//!             Some((level_ent, map_level, _)) => (level_ent, map_level),
+//!             None => continue,
+//!         };
+//!         let level = &map.project.levels[map_level.index];
+//!
+//!         // Find the entities layer
+//!         let entities_layer = level
+//!             .layer_instances
+//!             .as_ref() // get a reference to the layer instances
+//!             .unwrap() // Unwrap the option ( this could be None, if there are no layers )
+//!             .iter() // Iterate over the layers
+//!             .find(|&x| x.__identifier == "Entities") // Find the layer by its name
+//!             .unwrap(); // Unwrap it ( would be None if it could not find a layer "MyEntities" )
+//!
+//!         // Get the specific entity you want
+//!         let player_start = entities_layer
+//!             .entity_instances
+//!             .iter() // Iterate over our entities in the layer
+//!             .find(|x| x.__identifier == "Player_Spawn") // Find the one we want
+//!             .unwrap(); // Unwrap it
 //!
-//!         // If the map asset has finished loading
-//!         if let Some(map) = map_assets.get(handle) {
-//!             // This is the default level, but if you spawned a different level, put that ID here
-//!             let level_idx = 0;
-//!
-//!             // Get the level from the project
-//!             let level = &map.project.levels[level_idx];
-//!
-//!             // Find the entities layer
-//!             let entities_layer = level
-//!                 .layer_instances
-//!                 .as_ref() // get a reference to the layer instances
-//!                 .unwrap() // Unwrap the option ( this could be None, if there are no layers )
-//!                 .iter() // Iterate over the layers
-//!                 .filter(|&x| x.__identifier == "Entities") // Filter on the name of the layer
-//!                 .next() // Get it
-//!                 .unwrap(); // Unwrap it ( would be None if it could not find a layer "MyEntities" )
-//!
-//!             // Get the specific entity you want
-//!             let player_start = entities_layer
-//!                 .entity_instances
-//!                 .iter() // Iterate over our entities in the layer
-//!                 .filter(|x| x.__identifier == "Player_Spawn") // Find the one we want
-//!                 .next() // Get it
-//!                 .unwrap(); // Unwrap it
-//!
-//!             // Get the number of layers in the map and add one to it: this is how high we need to
-//!             // spawn the player so that he is on top of all the maps
-//!             let player_z = level.layer_instances.as_ref().unwrap().len() as f32 + 1.0;
-//!
-//!             // Spawn the entity!
-//!             commands.spawn().insert_bundle(SpriteBundle {
+//!         // Convert the entity's position in the map data to a world position. This takes care of
+//!         // the map's scale and centering, the layer's offset, and LDtk's +y meaning down.
+//!         let position = map_geometry
+//!             .level_layer(level_ent, "Entities")
+//!             .unwrap()
+//!             .ldtk_px_to_world(Vec2::new(
+//!                 player_start.px[0] as f32,
+//!                 player_start.px[1] as f32,
+//!             ));
+//!
+//!         // Place the player at the Z coordinate of the entities layer, so that it is drawn on
+//!         // top of the layers below it
+//!         let player_z = map_geometry.layer_z(map_ent, "Entities").unwrap();
+//!
+//!         // Spawn the entity!
+//!         commands
+//!             .spawn()
+//!             .insert_bundle(SpriteBundle {
 //!                 // Set your sprite stuff
-//!                 transform: Transform::from_xyz(
-//!                     // The player x position is the entity's x position from the map data
-//!                     player_start.px[0] as f32,
-//!                     // The player y position is the entity's y position from the map data, but
-//!                     // multiplied by negative one because in the LDtk map +y means down and not up.
-//!                     player_start.px[1] as f32 * -1.0,
-//!                     // Spawn the player with the z value we determined earlier
-//!                     player_z,
-//!                 ),
+//!                 transform: Transform::from_xyz(position.x, position.y, player_z),
 //!                 material: color_materials.add(ColorMaterial {
 //!                     texture: Some(asset_server.load("character.png")),
 //!                     ..Default::default()
 //!                 }),
 //!                 ..Default::default()
-//!             });
-//!         }
+//!             })
+//!             .insert(Player);
+//!
+//!         return;
 //!     }
 //! }
 //! ```
@@ -177,14 +357,44 @@
 
 use bevy::prelude::*;
 
+mod animation;
 mod asset;
+mod collision;
 mod components;
+mod entity;
+mod events;
+mod fields;
+mod geometry;
+mod int_grid;
+mod level;
+mod parallax;
+mod picking;
 mod pipeline;
 mod system;
+mod tiles;
+mod tileset;
+mod y_sort;
 
+pub use animation::*;
 pub use asset::*;
+pub use collision::*;
 pub use components::*;
+pub use entity::*;
+pub use events::*;
+pub use fields::*;
+pub use geometry::*;
+pub use int_grid::*;
+pub use level::*;
+pub use parallax::*;
+pub use picking::*;
 pub(crate) use pipeline::*;
+pub use pipeline::{
+    LdtkTilemapDisplayInfo, LdtkTilemapLayer, LdtkTilemapMapInfo, LdtkTilemapTileInfo,
+    LdtkTilemapTilesetInfo,
+};
+pub use tiles::*;
+pub use tileset::*;
+pub use y_sort::*;
 
 use pipeline::configure_pipeline;
 use system::add_systems;
@@ -195,8 +405,11 @@ pub struct LdtkPlugin;
 
 impl Plugin for LdtkPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        // Add asssets, systems, and graphics pipeline
+        // Add asssets, events, the entity and collider registries, systems, and graphics pipeline
         add_assets(app);
+        add_events(app);
+        add_entity_registry(app);
+        add_collider_registry(app);
         add_systems(app);
         configure_pipeline(app);
     }
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+/// The parallax settings of a rendered layer
+///
+/// The layer is offset towards the active 2D camera by `factor` times the distance between the
+/// camera and the center of the level, so that a factor of 0 is locked to the map, a factor of 1
+/// is locked to the camera, and a negative factor scrolls faster than the map. When `scaling` is
+/// enabled the layer is also scaled by `1 - factor` about the center of the level, like LDtk does.
+///
+/// The defaults for every layer are read from the `parallaxFactorX`, `parallaxFactorY`, and
+/// `parallaxScaling` fields of the layer definition in the project JSON, which were added in LDtk
+/// 1.0. Adding this component to a rendered layer entity overrides the defaults for that layer.
+/// The transforms of the layers that have neither are left alone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LdtkParallax {
+    /// The horizontal and vertical parallax factors, from -1.0 to 1.0
+    pub factor: Vec2,
+    /// Whether or not the layer is scaled along with the parallax factor
+    pub scaling: bool,
+}
+
+impl Default for LdtkParallax {
+    fn default() -> Self {
+        LdtkParallax {
+            factor: Vec2::ZERO,
+            scaling: true,
+        }
+    }
+}
+
+impl LdtkParallax {
+    /// Read the parallax settings from a layer definition in the project JSON, returning `None` if
+    /// it doesn't have any parallax settings
+    pub fn from_json(layer_def: &serde_json::Value) -> Option<Self> {
+        let factor_x = layer_def["parallaxFactorX"].as_f64();
+        let factor_y = layer_def["parallaxFactorY"].as_f64();
+        if factor_x.is_none() && factor_y.is_none() {
+            return None;
+        }
+
+        Some(LdtkParallax {
+            factor: Vec2::new(
+                factor_x.unwrap_or_default() as f32,
+                factor_y.unwrap_or_default() as f32,
+            ),
+            scaling: layer_def["parallaxScaling"].as_bool().unwrap_or(true),
+        })
+    }
+
+    /// Get the transform of a layer relative to its level entity, where `level_center` and
+    /// `camera` are the positions of the center of the level and the camera relative to the level
+    /// entity
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy::prelude::*;
+    /// use bevy_ldtk::*;
+    ///
+    /// let parallax = LdtkParallax {
+    ///     factor: Vec2::new(0.5, 0.0),
+    ///     scaling: false,
+    /// };
+    ///
+    /// // The layer follows the camera at half speed
+    /// let transform = parallax.layer_transform(Vec2::ZERO, Vec2::new(100.0, 40.0));
+    /// assert_eq!(transform.translation, Vec3::new(50.0, 0.0, 0.0));
+    /// ```
+    pub fn layer_transform(&self, level_center: Vec2, camera: Vec2) -> Transform {
+        let scale = if self.scaling {
+            Vec2::ONE - self.factor
+        } else {
+            Vec2::ONE
+        };
+
+        // Scale the layer about the level center and move it towards the camera
+        let translation =
+            level_center - level_center * scale + (camera - level_center) * self.factor;
+
+        Transform {
+            translation: translation.extend(0.0),
+            scale: scale.extend(1.0),
+            ..Default::default()
+        }
+    }
+}
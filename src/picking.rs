@@ -0,0 +1,225 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{LdtkLayerGeometry, LdtkMap, LdtkMapLevel, LdtkTileLayer, LdtkTilemapLayer};
+
+/// A tile found under a world position by the [`LdtkTilePicker`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkTilePick {
+    /// The rendered layer entity that the tile is in
+    pub layer_entity: Entity,
+    /// The level entity that the layer belongs to
+    pub level: Entity,
+    /// The identifier of the LDtk layer
+    pub identifier: String,
+    /// The index of the sublayer that the tile is in, with 0 being the lowest sublayer
+    pub sublayer_index: usize,
+    /// The coordinates of the cell that the tile is in, with (0, 0) being the top-left cell
+    pub cell: (u32, u32),
+    /// The uid of the layer's tileset, or `None` for IntGrid layers rendered with the colors of
+    /// their values
+    pub tileset_uid: Option<i32>,
+    /// The id of the tile in the tileset
+    pub tile_id: u32,
+    /// How the tile is flipped, in the same format as [`LdtkTilemapTileInfo::flip_bits`]
+    ///
+    /// [`LdtkTilemapTileInfo::flip_bits`]: crate::LdtkTilemapTileInfo::flip_bits
+    pub flip_bits: u32,
+    /// The custom data of the tile in the tileset, if it has any
+    pub custom_data: Option<String>,
+    /// The enum values that the tile is tagged with in the tileset
+    pub enum_tags: Vec<String>,
+}
+
+/// A system parameter used to find the tiles of the spawned layers that are under a world position
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_ldtk::*;
+///
+/// struct Player;
+///
+/// // Print the tiles that the player is standing on
+/// fn print_player_tiles(picker: LdtkTilePicker, players: Query<&Transform, With<Player>>) {
+///     for transform in players.iter() {
+///         for tile in picker.pick(transform.translation) {
+///             println!("{} {:?}: {}", tile.identifier, tile.cell, tile.tile_id);
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct LdtkTilePicker<'a> {
+    layers: Query<
+        'a,
+        (
+            Entity,
+            &'static LdtkTileLayer,
+            &'static LdtkTilemapLayer,
+            &'static GlobalTransform,
+            &'static Parent,
+        ),
+    >,
+    levels: Query<'a, (&'static LdtkMapLevel, &'static Parent)>,
+    maps: Query<'a, &'static Handle<LdtkMap>>,
+    map_assets: Res<'a, Assets<LdtkMap>>,
+}
+
+impl<'a> LdtkTilePicker<'a> {
+    /// Get the tiles of every spawned layer at a world position, ordered from the top-most tile to
+    /// the bottom-most tile by the world Z coordinate that they are drawn at
+    ///
+    /// The Z coordinate of the position is ignored.
+    pub fn pick(&self, world: Vec3) -> Vec<LdtkTilePick> {
+        let mut picks = self
+            .layers
+            .iter()
+            .filter_map(
+                |(layer_ent, tile_layer, tilemap_layer, transform, parent)| {
+                    let geometry = LdtkLayerGeometry::from_tilemap_layer(transform, tilemap_layer);
+                    let cell = geometry.world_to_cell(world)?;
+                    let pick =
+                        self.tile_pick(layer_ent, tile_layer, tilemap_layer, parent.0, cell)?;
+
+                    Some((layer_world_z(transform, tilemap_layer), pick))
+                },
+            )
+            .collect::<Vec<_>>();
+
+        sort_top_most_first(&mut picks);
+
+        picks.into_iter().map(|(_, pick)| pick).collect()
+    }
+
+    /// Get the tiles in the cell at the given coordinates of a layer in a spawned level, ordered
+    /// from the top-most tile to the bottom-most tile, where `level` is the entity with the
+    /// [`LdtkMapLevel`] component
+    pub fn tiles_at(&self, level: Entity, identifier: &str, x: u32, y: u32) -> Vec<LdtkTilePick> {
+        let mut picks = self
+            .layers
+            .iter()
+            .filter(|(_, tile_layer, _, _, parent)| {
+                parent.0 == level && tile_layer.identifier == identifier
+            })
+            .filter_map(|(layer_ent, tile_layer, tilemap_layer, transform, _)| {
+                // Rows of Y-sorted tiles and chunks only have some of the cells of the layer
+                let (origin_x, origin_y) = tile_layer.cell_origin();
+                let cell = (x.checked_sub(origin_x)?, y.checked_sub(origin_y)?);
+                let pick = self.tile_pick(layer_ent, tile_layer, tilemap_layer, level, cell)?;
+
+                Some((layer_world_z(transform, tilemap_layer), pick))
+            })
+            .collect::<Vec<_>>();
+
+        sort_top_most_first(&mut picks);
+
+        picks.into_iter().map(|(_, pick)| pick).collect()
+    }
+
+    /// Get the enum values that the tiles in the cell at the given coordinates of a layer in a
+    /// spawned level are tagged with, where `level` is the entity with the [`LdtkMapLevel`]
+    /// component
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use bevy::prelude::*;
+    /// use bevy_ldtk::*;
+    ///
+    /// struct Player {
+    ///     level: Entity,
+    ///     cell: (u32, u32),
+    /// }
+    ///
+    /// fn slide_on_ice(picker: LdtkTilePicker, players: Query<&Player>) {
+    ///     for player in players.iter() {
+    ///         let (x, y) = player.cell;
+    ///         if picker.tile_enum_tags(player.level, "Ground", x, y).contains(&"Ice".into()) {
+    ///             // Make the player slide
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn tile_enum_tags(&self, level: Entity, identifier: &str, x: u32, y: u32) -> Vec<String> {
+        let mut enum_tags = Vec::new();
+        for pick in self.tiles_at(level, identifier, x, y) {
+            for enum_tag in pick.enum_tags {
+                if !enum_tags.contains(&enum_tag) {
+                    enum_tags.push(enum_tag);
+                }
+            }
+        }
+
+        enum_tags
+    }
+
+    /// Get the tile in a cell of a rendered sublayer, if there is one, where the cell is relative
+    /// to the rendered layer entity rather than the LDtk layer
+    fn tile_pick(
+        &self,
+        layer_ent: Entity,
+        tile_layer: &LdtkTileLayer,
+        tilemap_layer: &LdtkTilemapLayer,
+        level: Entity,
+        (x, y): (u32, u32),
+    ) -> Option<LdtkTilePick> {
+        let tile = tilemap_layer.get_tile(x, y)?;
+        let (origin_x, origin_y) = tile_layer.cell_origin();
+
+        let tileset_uid = self.layer_tileset_uid(level, &tile_layer.identifier);
+        let tileset_meta =
+            tileset_uid.and_then(|uid| self.level_map(level)?.tileset_meta.get(&uid));
+
+        Some(LdtkTilePick {
+            layer_entity: layer_ent,
+            level,
+            identifier: tile_layer.identifier.clone(),
+            sublayer_index: tile_layer.sublayer_index,
+            cell: (origin_x + x, origin_y + y),
+            tileset_uid,
+            tile_id: tile.tile_index,
+            flip_bits: tile.flip_bits,
+            custom_data: tileset_meta
+                .and_then(|x| x.custom_data(tile.tile_index))
+                .map(String::from),
+            enum_tags: tileset_meta
+                .map(|x| x.enum_tags(tile.tile_index).to_vec())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Get the map asset that a spawned level belongs to
+    fn level_map(&self, level: Entity) -> Option<&LdtkMap> {
+        let (_, parent) = self.levels.get(level).ok()?;
+        self.map_assets.get(self.maps.get(parent.0).ok()?)
+    }
+
+    /// Get the uid of the tileset of a layer in a spawned level
+    fn layer_tileset_uid(&self, level: Entity, identifier: &str) -> Option<i32> {
+        let (map_level, _) = self.levels.get(level).ok()?;
+
+        self.level_map(level)?
+            .project
+            .levels
+            .get(map_level.index)?
+            .layer_instances
+            .as_ref()?
+            .iter()
+            .find(|x| x.__identifier == identifier)?
+            .__tileset_def_uid
+    }
+}
+
+/// Get the world Z coordinate that a rendered layer is drawn at, which includes the offset of its
+/// sublayer and, for rows of Y-sorted tiles, the Z coordinate of the row
+fn layer_world_z(transform: &GlobalTransform, tilemap_layer: &LdtkTilemapLayer) -> f32 {
+    transform
+        .mul_vec3(Vec3::new(0.0, 0.0, tilemap_layer.map_info.z))
+        .z
+}
+
+/// Sort picks by the world Z coordinate of their layers, putting the top-most tiles first
+fn sort_top_most_first(picks: &mut [(f32, LdtkTilePick)]) {
+    picks.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+}
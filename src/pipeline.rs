@@ -25,7 +25,7 @@ fn build_ldtk_tilemap_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescript
         texture::*,
     };
 
-    return PipelineDescriptor {
+    PipelineDescriptor {
         depth_stencil: Some(DepthStencilState {
             format: TextureFormat::Depth32Float,
             depth_write_enabled: true,
@@ -74,7 +74,7 @@ fn build_ldtk_tilemap_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescript
                 include_str!("pipeline/tilemap.frag"),
             ))),
         })
-    };
+    }
 }
 
 /// This is the struct containing all of the information sent to the shaders that will render our
@@ -95,11 +95,13 @@ pub struct LdtkTilemapLayer {
     /// The list of all of the tiles in the map
     #[render_resources(buffer)]
     pub tiles: Vec<LdtkTilemapTileInfo>,
+    /// How the layer is displayed
+    pub display_info: LdtkTilemapDisplayInfo,
 }
 
 /// Information about the tilemap used by the GPU shaders
 #[repr(C)]
-#[derive(RenderResource, Default, Debug, Clone, Copy)]
+#[derive(RenderResource, Default, Debug, Clone, Copy, PartialEq)]
 pub struct LdtkTilemapMapInfo {
     /// The number of tiles wide the map is
     pub width: u32,
@@ -109,15 +111,51 @@ pub struct LdtkTilemapMapInfo {
     pub layer_index: u32,
     /// The index of this sublayer, counted starting at 0, with 0 being the lowest sublayer
     pub sublayer_index: u32,
+    /// The Z coordinate of this sublayer relative to the level, taken from the map's
+    /// [`LayerZStrategy`]
+    ///
+    /// [`LayerZStrategy`]: crate::LayerZStrategy
+    pub z: f32,
     /// Whether or not to center the map around the origin ( Using the `u32` type because bools
     /// don't seem to work right for some reason. `0` means `false` and `1` means `true` )
     pub center_map: u32,
+    /// The number of pixels wide ( and tall ) a cell in the layer grid is
+    pub grid_size: u32,
+    /// The number of pixels to offset the layer along the X axis, with +x being right
+    pub offset_x: i32,
+    /// The number of pixels to offset the layer along the Y axis, with +y being down
+    pub offset_y: i32,
+    /// The number of tiles wide the whole layer is. This is larger than `width` for the rows and
+    /// chunks that a layer is split into, and is used to center them in the same place as the rest
+    /// of the layer. `0` means that this is the same as `width`.
+    pub layer_width: u32,
+    /// The number of tiles tall the whole layer is, like `layer_width`. `0` means that this is the
+    /// same as `height`.
+    pub layer_height: u32,
+}
+
+impl LdtkTilemapMapInfo {
+    /// Get the number of tiles wide and tall the whole layer is
+    pub fn layer_size(&self) -> UVec2 {
+        UVec2::new(
+            if self.layer_width == 0 {
+                self.width
+            } else {
+                self.layer_width
+            },
+            if self.layer_height == 0 {
+                self.height
+            } else {
+                self.layer_height
+            },
+        )
+    }
 }
 unsafe impl Byteable for LdtkTilemapMapInfo {}
 
 /// Information about a layer's tileset used by the GPU shaders
 #[repr(C)]
-#[derive(RenderResource, Default, Debug, Clone, Copy)]
+#[derive(RenderResource, Default, Debug, Clone, Copy, PartialEq)]
 pub struct LdtkTilemapTilesetInfo {
     /// The number of tiles wide the tileset is
     pub width: u32,
@@ -125,12 +163,43 @@ pub struct LdtkTilemapTilesetInfo {
     pub height: u32,
     /// The number of pixels wide ( and tall ) a tile in the tileset grid is
     pub grid_size: u32,
+    /// The number of pixels between each tile in the tileset
+    pub spacing: u32,
+    /// The number of pixels between the edge of the tileset image and the tiles
+    pub padding: u32,
 }
 unsafe impl Byteable for LdtkTilemapTilesetInfo {}
 
+/// Information about how a layer is displayed used by the GPU shaders
+///
+/// The opacity and visibility are taken from the LDtk layer when the layer is spawned, and can be
+/// changed at runtime to fade or hide the layer.
+#[repr(C)]
+#[derive(RenderResource, Debug, Clone, Copy, PartialEq)]
+pub struct LdtkTilemapDisplayInfo {
+    /// The color that the tiles are multiplied by, as linear RGBA
+    pub tint: [f32; 4],
+    /// The opacity of the layer, from 0.0 to 1.0
+    pub opacity: f32,
+    /// Whether or not the layer is visible ( Using the `u32` type because bools don't seem to work
+    /// right for some reason. `0` means `false` and `1` means `true` )
+    pub visible: u32,
+}
+unsafe impl Byteable for LdtkTilemapDisplayInfo {}
+
+impl Default for LdtkTilemapDisplayInfo {
+    fn default() -> Self {
+        LdtkTilemapDisplayInfo {
+            tint: [1.0; 4],
+            opacity: 1.0,
+            visible: 1,
+        }
+    }
+}
+
 /// The information about a specific tile in a map layer
 #[repr(C)]
-#[derive(RenderResource, Default, Debug, Clone, Copy)]
+#[derive(RenderResource, Default, Debug, Clone, Copy, PartialEq)]
 pub struct LdtkTilemapTileInfo {
     /// The index of the tile image in the tileset texture
     pub tile_index: u32,
@@ -146,14 +215,16 @@ unsafe impl Byteable for LdtkTilemapTileInfo {}
 /// This module is created just to hold the constants for our render graph node names
 pub mod node {
     /// The name of the tilemap render graph node
-    pub const LDTK_TILEMAP: &'static str = "ldtk_tile_map";
+    pub const LDTK_TILEMAP: &str = "ldtk_tile_map";
 }
 
 /// Configure the render pipeline for LDtk maps
 pub(crate) fn configure_pipeline(app: &mut AppBuilder) {
     // Get the app resources
     let world_cell = app.world_mut().cell();
-    let mut pipelines = world_cell.get_resource_mut::<Assets<PipelineDescriptor>>().unwrap();
+    let mut pipelines = world_cell
+        .get_resource_mut::<Assets<PipelineDescriptor>>()
+        .unwrap();
     let mut shaders = world_cell.get_resource_mut::<Assets<Shader>>().unwrap();
     let mut render_graph = world_cell.get_resource_mut::<RenderGraph>().unwrap();
 
@@ -30,12 +30,20 @@ layout(set = 2, binding = 1) uniform LdtkTilemapLayer_map_info {
     uint map_height_tiles;
     uint layer_index;
     uint sublayer_index;
+    float layer_z;
     uint center_map;
+    uint map_grid_size;
+    int map_offset_x;
+    int map_offset_y;
+    uint layer_width_tiles;
+    uint layer_height_tiles;
 };
 layout(set = 2, binding = 2) uniform LdtkTilemapLayer_tileset_info {
     uint tileset_width_tiles;
     uint tileset_height_tiles;
     uint tileset_grid_size;
+    uint tileset_spacing;
+    uint tileset_padding;
 };
 // These texture uniforms are automatically added by Bevy to represent the `Handle<Texture>` that
 // was in our corresponding Rust struct.
@@ -48,11 +56,21 @@ struct TileInfo {
 layout(set = 2, binding = 5) buffer LdtkTilemapLayer_tiles {
     TileInfo[] map_tiles;
 };
+layout(set = 2, binding = 6) uniform LdtkTilemapLayer_display_info {
+    vec4 layer_tint;
+    float layer_opacity;
+    uint layer_visible;
+};
 
 void main() {
     // We use the maximum number in a uint to represent an empty tile, and assign it to a constant
     uint EMPTY_TILE_IDX = 4294967295;
 
+    // Don't render anything for hidden layers
+    if (layer_visible == 0) {
+        discard;
+    }
+
     // Create a map size vector from the width and height of the map
     vec2 map_size = vec2(map_width_tiles, map_height_tiles);
 
@@ -84,11 +102,11 @@ void main() {
         // And combine that to our tileset tile vector
         vec2 tileset_tile = vec2(tileset_tile_x, tileset_tile_y);
 
-        // Next calculate the size of a map tile, as a fraction of the total size of the mesh,
-        // which is betwen 0 and 1.
-        vec2 map_tile_size = vec2(1 / map_width_tiles, 1 / map_height_tiles);
-        // And calculate the size of a tileset tile as a fraction of its texture size
-        vec2 tileset_tile_size = vec2(1 / float(tileset_width_tiles), 1 / float(tileset_height_tiles));
+        // Get the size of the tileset texture in pixels
+        vec2 tileset_size_px = vec2(textureSize(
+            sampler2D(LdtkTilemapLayer_texture, LdtkTilemapLayer_texture_sampler),
+            0
+        ));
 
         // Flip the x UV of the whole tileset so that it lines up with our left-to-right interpretation
         // of the tilesheet indexes
@@ -112,21 +130,30 @@ void main() {
         // coordinate from the tileset for the whole pixel in the map. In other words, grab the
         // center of the pixel in our tileset to get the color. This helps prevent bleeding colors
         // in between tiles in the map.
-        vec2 pixel_tile_uv = 
+        vec2 pixel_tile_px = 
             // round the tile coordinate down to the closest pixel
-            floor(tile_uv * tileset_grid_size) / tileset_grid_size
+            floor(tile_uv * tileset_grid_size)
             // and add half a pixel's width to grab the center of the pixel in the tileset
-            + 0.5 / float(tileset_grid_size);
+            + 0.5;
+
+        // Calculate the pixel position of the top-left corner of the tile in the tileset, skipping
+        // over the padding around the edge of the tileset image and the spacing between each tile.
+        vec2 tileset_tile_px =
+            float(tileset_padding) + tileset_tile * float(tileset_grid_size + tileset_spacing);
 
         // Sample our fragment from the tileset texture
         o_Color = texture(
             sampler2D(LdtkTilemapLayer_texture, LdtkTilemapLayer_texture_sampler),
             // The UV coordinate calculated here is the location from the tileset that we take our
-            // pixels. We calculate it by offsetting the UV according to the location of the tile in
-            // the tileset, and then adding the tile UV scaled to the size of a tilemap tile.
-            tileset_tile * tileset_tile_size + pixel_tile_uv * tileset_tile_size
+            // pixels. We calculate it by offsetting the pixel in the tile by the location of the
+            // tile in the tileset, and then dividing by the size of the tileset texture.
+            (tileset_tile_px + pixel_tile_px) / tileset_size_px
         );
 
+        // Apply the layer tint and opacity
+        o_Color *= layer_tint;
+        o_Color.a *= layer_opacity;
+
     // If this is an empty tile, just make it transparent
     } else {
         o_Color = vec4(0, 1, 0, 0);
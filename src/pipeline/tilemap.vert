@@ -34,12 +34,20 @@ layout(set = 2, binding = 1) uniform LdtkTilemapLayer_map_info {
     uint map_height_tiles;
     uint layer_index;
     uint sublayer_index;
+    float layer_z;
     uint center_map;
+    uint map_grid_size;
+    int map_offset_x;
+    int map_offset_y;
+    uint layer_width_tiles;
+    uint layer_height_tiles;
 };
 layout(set = 2, binding = 2) uniform LdtkTilemapLayer_tileset_info {
     uint tileset_width_tiles;
     uint tileset_height_tiles;
     uint tileset_grid_size;
+    uint tileset_spacing;
+    uint tileset_padding;
 };
 // These texture uniforms are automatically added by Bevy to represent the `Handle<Texture>` that
 // was in our corresponding Rust struct.
@@ -52,6 +60,11 @@ struct TileInfo {
 layout(set = 2, binding = 5) buffer LdtkTilemapLayer_tiles {
     TileInfo[] map_tiles;
 };
+layout(set = 2, binding = 6) uniform LdtkTilemapLayer_display_info {
+    vec4 layer_tint;
+    float layer_opacity;
+    uint layer_visible;
+};
 
 // ## Outputs
 //
@@ -59,37 +72,54 @@ layout(set = 2, binding = 5) buffer LdtkTilemapLayer_tiles {
 layout(location = 0) out vec2 v_Uv;
 
 void main() {
-    float scale_factor = map_scale * float(tileset_grid_size);
+    // The size of a map grid cell on screen
+    float scale_factor = map_scale * float(map_grid_size);
 
     // Calculate a base position for the vertice, scaling it to match the aspect ratio of the
     // tilemap.
     vec3 pos = vec3(
         Vertex_Position.x * map_width_tiles * scale_factor,
         Vertex_Position.y * map_height_tiles * scale_factor,
-        // Stack each successive layer on top of the ones before it, setting it one unit higher.
-        // We also need to add a teeny tiny bit of space between sublayers to make them stack on
-        // each-other properly.
-        Vertex_Position.z + float(layer_index) + float(sublayer_index * 0.00001)
+        // Stack each successive layer on top of the ones before it. The Z coordinate of every
+        // layer and sublayer is worked out from the map's config before it is sent to the GPU.
+        Vertex_Position.z + layer_z
+    );
+
+    // Offset the map so that the top-left corner of the map is at (0, 0)
+    pos = vec3(
+        // Offset the X and Y by half of the map dimensions to un-center it
+        pos.x + map_width_tiles * scale_factor / 2.0,
+        pos.y - map_height_tiles * scale_factor / 2.0,
+        pos.z
     );
 
-    // If the map should not be centered, offset it so that the top-left corner of the map is
-    // at (0, 0)
-    if (center_map == 0) {
+    // If the map should be centered, center the whole layer around the origin. Rows and chunks of
+    // a layer only cover part of it, so we use the size of the whole layer to center them in the
+    // same place as the rest of the layer.
+    if (center_map == 1) {
+        uint layer_width = layer_width_tiles == 0 ? map_width_tiles : layer_width_tiles;
+        uint layer_height = layer_height_tiles == 0 ? map_height_tiles : layer_height_tiles;
         pos = vec3(
-            // Offset the X and Y by half of the map dimensions to un-center it
-            pos.x + map_width_tiles * scale_factor / 2.0,
-            pos.y - map_height_tiles * scale_factor / 2.0,
+            pos.x - layer_width * scale_factor / 2.0,
+            pos.y + layer_height * scale_factor / 2.0,
             pos.z
         );
     }
 
+    // Offset the layer by the layer's pixel offset. LDtk's +y means down, so we flip the y offset
+    pos = vec3(
+        pos.x + float(map_offset_x) * map_scale,
+        pos.y - float(map_offset_y) * map_scale,
+        pos.z
+    );
+
     // Simply forward our v_Uv out variable from the input Vertex_Uv unchanged.
     v_Uv = Vertex_Uv;
 
     // Set the position of the vertex
     gl_Position = 
         // Add the view and model projections, and multiply the position by the map scale and the
-        // tileset grid size. The grid size multiplication makes sure that grid pixels correspond to
+        // layer grid size. The grid size multiplication makes sure that grid pixels correspond to
         // pixels on the screen, assuming the map_scale is set to 1.
         ViewProj * Model * vec4(pos, 1);
 }
@@ -1,7 +1,17 @@
-use asset::LdtkMap;
+use asset::{LdtkLevel, LdtkMap};
 use bevy::{
-    render::pipeline::RenderPipeline, render::texture::FilterMode,
-    render::texture::SamplerDescriptor, utils::HashMap,
+    render::camera::{Camera, OrthographicProjection},
+    render::pipeline::RenderPipeline,
+    render::render_graph::base::camera::CAMERA_2D,
+    render::texture::FilterMode,
+    render::texture::SamplerDescriptor,
+    utils::HashMap,
+    utils::HashSet,
+};
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
 };
 
 use crate::*;
@@ -9,35 +19,35 @@ use crate::*;
 /// Add the Ldtk map systems to the app builder
 pub(crate) fn add_systems(app: &mut AppBuilder) {
     app.add_system(process_ldtk_maps.system())
-       .add_system(process_ldtk_tilesets.system())
-       .add_system(hot_reload_maps.system());
-}
-
-#[derive(Default)]
-struct State {
-    // atlasLoaded: HashSet<Handle<LdtkMap>>,
+        .add_system(process_ldtk_tilesets.system())
+        .add_system(hot_reload_maps.system())
+        .add_system(respawn_changed_maps.system())
+        .add_system(stream_levels.system())
+        .add_system(track_levels.system())
+        .add_system(pick_cursor_tiles.system())
+        .add_system(animate_tiles.system())
+        .add_system(apply_parallax.system())
+        .add_system(y_sort_entities.system());
 }
 
-/// Indicates that the tilesets for an [`LdtkMap`] has been loaded.
-struct TilesetsLoaded;
-
 /// A system that watches for loaded LDTK map assets and updates the texture filtering mode to
 /// prevent lines between the tiles.
 fn process_ldtk_tilesets(
     mut commands: Commands,
-    query: Query<(Entity, &Handle<LdtkMap>), Without<TilesetsLoaded>>,
+    query: Query<(Entity, &Handle<LdtkMap>, &LdtkMapConfig), Without<TilesetsLoaded>>,
     mut textures: ResMut<Assets<Texture>>,
     ldtk_maps: Res<Assets<LdtkMap>>,
+    mut map_events: EventWriter<LdtkMapEvent>,
 ) {
     // Loop through all of the map handles
-    for (map_ent, map_handle) in query.iter() {
+    for (map_ent, map_handle, config) in query.iter() {
         // Get the map if it has loaded
         if let Some(map) = ldtk_maps.get(map_handle) {
             // Count how many textures have loaded for this map
             let mut loaded = 0;
 
             // Loop through all the textures
-            for (_, texture_handle) in &map.tile_sets {
+            for texture_handle in map.tile_sets.values() {
                 // If the texture has loaded
                 if let Some(texture) = textures.get_mut(texture_handle) {
                     // Make sure that the filtering mode is set to `Nearest` to prevent tiles from
@@ -58,267 +68,2559 @@ fn process_ldtk_tilesets(
             // we don't process this map again
             if loaded == map.tile_sets.len() {
                 commands.entity(map_ent).insert(TilesetsLoaded);
+                map_events.send(LdtkMapEvent::TilesetsReady {
+                    map: map_ent,
+                    levels: config.levels.clone(),
+                });
             }
         }
     }
 }
 
-struct LdtkMapHasLoaded;
-
-/// Holds a `Handle<LdtkMap>` in a newtype for the tilemap layers so that iterating over map handles
-/// will only iterate over maps and not layers.
-struct LayerMapHandle(Handle<LdtkMap>);
-
 /// This system spawns the map layers for every unloaded entity with an LDtk map
+#[allow(clippy::too_many_arguments)]
 fn process_ldtk_maps(
     mut commands: Commands,
     mut clear_color: ResMut<ClearColor>,
     mut new_maps: Query<(Entity, &Handle<LdtkMap>, &LdtkMapConfig), Without<LdtkMapHasLoaded>>,
     map_assets: Res<Assets<LdtkMap>>,
+    entity_registry: Res<LdtkEntityRegistry>,
+    collider_registry: Res<LdtkColliderRegistry>,
+    mut error_events: EventWriter<LdtkMapError>,
+    mut map_events: EventWriter<LdtkMapEvent>,
 ) {
     // Loop through all of the maps
     for (ent, map_handle, config) in new_maps.iter_mut() {
         // Get the map asset, if available
         if let Some(map) = map_assets.get(map_handle) {
-            let project = &map.project;
-            let grid_size = map.project.default_grid_size;
-
-            // Create a hasmap mapping tileset def uid's to the tileset definition and it's texture handle
-            let mut tilesets = HashMap::default();
-
-            // Load all the tilesets
-            for (tileset_name, texture_handle) in &map.tile_sets {
-                // Get the tileset info
-                let tileset_info = project
-                    .defs
-                    .tilesets
-                    .iter()
-                    .filter(|x| &x.identifier == tileset_name)
-                    .next()
-                    .expect("Could not find tilset inside of map data");
-
-                // Insert it into the tileset map
-                tilesets.insert(tileset_info.uid, (tileset_info, texture_handle.clone()));
-            }
-
-            // Get the level that we are to display
-            let level = map.project.levels.get(config.level as usize).unwrap();
-
-            // If the clear color should be set from the map background, set it
-            if config.set_clear_color {
-                *clear_color = ClearColor(
-                    Color::hex(
-                        level
-                            .bg_color
-                            .as_ref()
-                            .unwrap_or(&map.project.default_level_bg_color)
-                            .strip_prefix("#")
-                            .expect("Invalid background color"),
-                    )
-                    .expect("Invalid background color"),
-                );
+            map_events.send(LdtkMapEvent::AssetLoaded {
+                map: ent,
+                levels: config.levels.clone(),
+            });
+
+            // Spawn the map, reporting any errors that prevented it from spawning
+            match spawn_map(
+                &mut commands,
+                ent,
+                map,
+                config,
+                &entity_registry,
+                &collider_registry,
+            ) {
+                // If the clear color should be set from the map background, set it
+                Ok(background_color) => {
+                    if config.set_clear_color {
+                        *clear_color = ClearColor(background_color);
+                    }
+
+                    map_events.send(LdtkMapEvent::Spawned {
+                        map: ent,
+                        levels: config.levels.clone(),
+                    });
+                }
+                Err(error) => error_events.send(LdtkMapError { entity: ent, error }),
             }
 
-            // Loop through the layers in the selected level
-            for (z, layer) in level
-                .layer_instances
-                .as_ref()
-                .unwrap()
+            // Mark the map as having been loaded so that we don't process it again. If the map
+            // failed to spawn we don't try again until it is modified.
+            commands.entity(ent).insert(LdtkMapHasLoaded);
+        }
+    }
+}
+
+/// The map data used while spawning the levels of a map
+struct MapSpawnContext<'a> {
+    map: &'a LdtkMap,
+    config: &'a LdtkMapConfig,
+    /// A mapping of tileset def uid's to the tileset definition and it's texture handle
+    tilesets: HashMap<i32, (&'a ldtk::TilesetDef, Handle<Texture>)>,
+    /// A mapping of layer def uid's to the layer definitions
+    layer_defs: HashMap<i32, &'a ldtk::LayerDef>,
+    entity_registry: &'a LdtkEntityRegistry,
+    collider_registry: &'a LdtkColliderRegistry,
+}
+
+impl<'a> MapSpawnContext<'a> {
+    /// Get the Z coordinate of a layer relative to the level entity, where `layer_index` is the
+    /// layer number counted from the bottom layer
+    fn layer_z(&self, layer: &ldtk::LayerInstance, layer_index: usize) -> f32 {
+        self.config.layer_z.layer_z(
+            &layer.__identifier,
+            layer_index,
+            self.map.project.defs.layers.len(),
+        )
+    }
+
+    /// Collect the tileset and layer definitions of a map
+    fn new(
+        map: &'a LdtkMap,
+        config: &'a LdtkMapConfig,
+        entity_registry: &'a LdtkEntityRegistry,
+        collider_registry: &'a LdtkColliderRegistry,
+    ) -> Result<Self, LdtkMapSpawnError> {
+        let project = &map.project;
+
+        // Create a hasmap mapping tileset def uid's to the tileset definition and texture handle
+        let mut tilesets = HashMap::default();
+
+        // Load all the tilesets
+        for (tileset_name, texture_handle) in &map.tile_sets {
+            // Get the tileset info
+            let tileset_info = project
+                .defs
+                .tilesets
                 .iter()
-                .rev() // Reverse the layer order so that the bottom layer is first
-                .enumerate()
-            {
-                // Get the information for the tileset associated to this layer
-                let (tileset_info, tileset_texture) = if let Some(uid) = layer.__tileset_def_uid {
-                    tilesets.get(&uid).expect("Missing tileset").clone()
+                .find(|x| &x.identifier == tileset_name)
+                .ok_or_else(|| LdtkMapSpawnError::MissingTileset(tileset_name.clone()))?;
 
-                // Skip this layer if there is no tileset texture for it
-                } else {
-                    continue;
-                };
+            // Insert it into the tileset map
+            tilesets.insert(tileset_info.uid, (tileset_info, texture_handle.clone()));
+        }
+
+        // Create a hashmap mapping layer def uid's to the layer definitions
+        let layer_defs = project
+            .defs
+            .layers
+            .iter()
+            .map(|x| (x.uid, x))
+            .collect::<HashMap<_, _>>();
+
+        Ok(MapSpawnContext {
+            map,
+            config,
+            tilesets,
+            layer_defs,
+            entity_registry,
+            collider_registry,
+        })
+    }
+
+    /// Get the iids of the entity instances of a layer in a level, if every instance has one
+    fn entity_iids(&self, level_index: usize, layer: &ldtk::LayerInstance) -> Option<&[String]> {
+        let level_uid = self.map.project.levels[level_index].uid;
+        let iids = self
+            .map
+            .entity_iids
+            .get(&level_uid)?
+            .get(&layer.layer_def_uid)?;
+
+        // Ignore the iids if they are out of date with the layer
+        if iids.len() == layer.entity_instances.len() {
+            Some(iids)
+        } else {
+            None
+        }
+    }
+
+    /// Make sure that a level has its layers, and that the tilesets and layer definitions used by
+    /// the layers exist
+    fn validate_level(&self, level_index: usize) -> Result<(), LdtkMapSpawnError> {
+        let level = &self.map.project.levels[level_index];
+
+        let layers = level
+            .layer_instances
+            .as_ref()
+            .ok_or_else(|| LdtkMapSpawnError::MissingLayers(level.identifier.clone()))?;
+
+        for layer in layers {
+            if let Some(uid) = layer.__tileset_def_uid {
+                if !self.tilesets.contains_key(&uid) {
+                    return Err(LdtkMapSpawnError::MissingTilesetUid(uid));
+                }
+            }
+
+            if !self.layer_defs.contains_key(&layer.layer_def_uid) {
+                return Err(LdtkMapSpawnError::MissingLayerDefinition(
+                    layer.layer_def_uid,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Spawn a level entity with all of its layers as a child of the map entity, returning the
+    /// level entity
+    ///
+    /// The level must have been validated with [`MapSpawnContext::validate_level`].
+    fn spawn_level(&self, commands: &mut Commands, map_ent: Entity, level_index: usize) -> Entity {
+        let project = &self.map.project;
+        let level = &project.levels[level_index];
+        let origin = level_origin(project, level_index, self.config);
+
+        // Spawn the level entity, keeping track of its layers for hot reloading
+        let layer_sources = level
+            .layer_instances
+            .iter()
+            .flatten()
+            .map(|x| LayerSource::new(self, level_index, x))
+            .collect();
+        let level_ent = commands
+            .spawn()
+            .insert(LdtkMapLevel {
+                index: level_index,
+                uid: level.uid,
+                identifier: level.identifier.clone(),
+                iid: self.map.level_iids.get(&level.uid).cloned(),
+                bounds: level_bounds(project, level_index, self.config),
+            })
+            .insert(Transform::from_xyz(origin.x, origin.y, 0.))
+            .insert(GlobalTransform::default())
+            .insert(SpawnedLevelLayers(layer_sources))
+            .id();
+
+        // Add the level as a child of the LDtk map entity
+        commands.entity(map_ent).push_children(&[level_ent]);
+
+        spawn_level_layers(commands, self, level_ent, level_index);
+
+        level_ent
+    }
+}
+
+/// Spawn the selected levels of an LDtk map as children of the map entity, returning the
+/// background color of the first spawned level
+///
+/// All of the map data is validated before anything is spawned, so that a map that fails to spawn
+/// is left completely unspawned.
+fn spawn_map(
+    commands: &mut Commands,
+    ent: Entity,
+    map: &LdtkMap,
+    config: &LdtkMapConfig,
+    entity_registry: &LdtkEntityRegistry,
+    collider_registry: &LdtkColliderRegistry,
+) -> Result<Color, LdtkMapSpawnError> {
+    let project = &map.project;
+    let ctx = MapSpawnContext::new(map, config, entity_registry, collider_registry)?;
+
+    // Get the indexes of the levels that we are to display
+    let level_indexes = config.levels.select(map)?;
+
+    // Make sure that all of the selected levels can be spawned before we spawn anything
+    for &level_index in &level_indexes {
+        ctx.validate_level(level_index)?;
+    }
+
+    // Get the background color of the first level
+    let background_color = parse_color(
+        level_indexes
+            .first()
+            .and_then(|&i| project.levels[i].bg_color.as_ref())
+            .unwrap_or(&project.default_level_bg_color),
+    )?;
+
+    // Spawn every selected level
+    for level_index in level_indexes {
+        ctx.spawn_level(commands, ent, level_index);
+    }
+
+    Ok(background_color)
+}
+
+/// Identifies what part of a level an entity was spawned from, so that it can be updated in place
+/// when the map is hot reloaded
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum SpawnedFrom {
+    /// A sublayer of a rendered layer
+    Tiles {
+        layer_def_uid: i32,
+        sublayer_index: usize,
+    },
+    /// The values of an IntGrid layer
+    IntGrid { layer_def_uid: i32 },
+    /// An entity instance that has an iid
+    EntityIid { layer_def_uid: i32, iid: String },
+    /// An entity instance in a layer without iids. Entity instances don't have iids in LDtk 0.8.1,
+    /// so they are identified by their definition and their index among the instances of that
+    /// definition in the layer.
+    Entity {
+        layer_def_uid: i32,
+        def_uid: i32,
+        index: usize,
+    },
+    /// A row of Y-sorted tiles split out of a rendered sublayer
+    YSortedRow {
+        layer_def_uid: i32,
+        sublayer_index: usize,
+        row: u32,
+    },
+    /// A chunk of a rendered sublayer, identified by the coordinates of its top-left cell
+    TileChunk {
+        layer_def_uid: i32,
+        sublayer_index: usize,
+        origin: (u32, u32),
+    },
+}
+
+impl SpawnedFrom {
+    /// Get the uid of the definition of the layer that the entity was spawned from
+    fn layer_def_uid(&self) -> i32 {
+        match self {
+            SpawnedFrom::Tiles { layer_def_uid, .. }
+            | SpawnedFrom::IntGrid { layer_def_uid }
+            | SpawnedFrom::EntityIid { layer_def_uid, .. }
+            | SpawnedFrom::Entity { layer_def_uid, .. }
+            | SpawnedFrom::YSortedRow { layer_def_uid, .. }
+            | SpawnedFrom::TileChunk { layer_def_uid, .. } => *layer_def_uid,
+        }
+    }
+}
+
+/// The map data that the layers of a level were spawned from, in the order that the layers appear
+/// in the level
+pub(crate) struct SpawnedLevelLayers(Vec<LayerSource>);
+
+/// A summary of the map data that a layer was spawned from, used to leave the layers that haven't
+/// changed alone when the map is hot reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LayerSource {
+    layer_def_uid: i32,
+    /// A hash of the layer instance, its layer and tileset definitions, the custom data, enum tags
+    /// and tile animations of its tileset, and the iids of its entity instances
+    hash: u64,
+}
+
+impl LayerSource {
+    /// Summarize the map data that a layer is spawned from
+    fn new(ctx: &MapSpawnContext, level_index: usize, layer: &ldtk::LayerInstance) -> Self {
+        let tileset_uid = layer.__tileset_def_uid;
+        let mut hasher = DefaultHasher::new();
+
+        // The LDtk types don't implement `Hash`, so hash their JSON instead
+        serde_json::to_vec(&(
+            layer,
+            ctx.layer_defs.get(&layer.layer_def_uid),
+            tileset_uid
+                .and_then(|uid| ctx.tilesets.get(&uid))
+                .map(|x| x.0),
+        ))
+        .expect("Could not serialize layer")
+        .hash(&mut hasher);
+
+        // Hash the maps sorted by key so that the hash doesn't depend on their iteration order
+        if let Some(meta) = tileset_uid.and_then(|uid| ctx.map.tileset_meta.get(&uid)) {
+            sorted_by_key(&meta.custom_data).hash(&mut hasher);
+            sorted_by_key(&meta.enum_tags).hash(&mut hasher);
+        }
+        if let Some(animations) = tileset_uid.and_then(|uid| ctx.map.tile_animations.get(&uid)) {
+            for (tile_id, animation) in sorted_by_key(animations) {
+                tile_id.hash(&mut hasher);
+                for frame in &animation.frames {
+                    (frame.tile_id, frame.duration.to_bits()).hash(&mut hasher);
+                }
+            }
+        }
+
+        ctx.entity_iids(level_index, layer).hash(&mut hasher);
 
-                // Create a list of all the tiles in the layer
-                let tiles = if !layer.auto_layer_tiles.is_empty() {
-                    &layer.auto_layer_tiles
-                } else if !layer.grid_tiles.is_empty() {
-                    &layer.grid_tiles
-                } else {
-                    // Skip the layer if there are no tiles for it
-                    continue;
+        LayerSource {
+            layer_def_uid: layer.layer_def_uid,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+/// Get the entries of a map sorted by key
+fn sorted_by_key<K: Ord, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|(key, _)| *key);
+    entries
+}
+
+/// Spawn the layers of a level as children of the level entity
+fn spawn_level_layers(
+    commands: &mut Commands,
+    ctx: &MapSpawnContext,
+    level_ent: Entity,
+    level_index: usize,
+) {
+    // The layers were checked for before spawning the map
+    let layers = ctx.map.project.levels[level_index]
+        .layer_instances
+        .as_ref()
+        .expect("Missing level layers");
+
+    // Loop through the layers in the level
+    for (layer_index, layer) in layers
+        .iter()
+        .rev() // Reverse the layer order so that the bottom layer is first
+        .enumerate()
+    {
+        // Spawn the registered bundles for the entities in this layer
+        for (key, instance) in layer_entity_instances(layer, ctx.entity_iids(level_index, layer)) {
+            spawn_ldtk_entity(commands, ctx, level_ent, key, instance, layer, layer_index);
+        }
+
+        // Spawn the IntGrid values for IntGrid layers
+        let int_grid = layer_int_grid(ctx, layer);
+        if let Some(int_grid) = &int_grid {
+            spawn_int_grid(
+                commands,
+                ctx,
+                level_ent,
+                int_grid.clone(),
+                layer,
+                layer_index,
+            );
+        }
+
+        // Spawn every sublayer of the layer that should be rendered
+        for (key, tilemap_layer) in
+            keyed_layer_tilemaps(ctx, layer, level_index, layer_index, int_grid.as_ref())
+        {
+            spawn_tilemap_layer(commands, ctx, level_ent, key, layer, tilemap_layer);
+        }
+    }
+}
+
+/// Get the entity instances of a layer, along with the keys that identify them
+///
+/// The instances are identified by their iids when they have them, and by their index otherwise.
+fn layer_entity_instances<'a>(
+    layer: &'a ldtk::LayerInstance,
+    iids: Option<&[String]>,
+) -> Vec<(SpawnedFrom, &'a ldtk::EntityInstance)> {
+    if let Some(iids) = iids {
+        return iids
+            .iter()
+            .zip(&layer.entity_instances)
+            .map(|(iid, instance)| {
+                let key = SpawnedFrom::EntityIid {
+                    layer_def_uid: layer.layer_def_uid,
+                    iid: iid.clone(),
                 };
 
-                // Create a vector of "sublayers", each of which has a mapping of the (x, y)
-                // coordinate of the tile to the tile information for that location. Because LDtk's
-                // auto-mapped tiles support having multiple tiles in the same cell, we may need
-                // more than one sublayer for the overlayed tiles.
-                let mut sublayers: Vec<HashMap<(u32, u32), LdtkTilemapTileInfo>> = Vec::new();
-
-                // The width of the tileset in tiles
-                let tileset_width_tiles = (tileset_info.px_wid / grid_size) as u32;
-
-                // For every tile in the layer
-                for tile in tiles {
-                    // Get the x and y position of the tile in the map
-                    let tileset_tile_x = (tile.src[0] / grid_size) as u32;
-                    let tileset_tile_y = (tile.src[1] / grid_size) as u32;
-
-                    // Add the tile and it's info to the (x, y) position in our tiles HashMap, and
-                    // add it to the list of tiles in that square
-                    let mut sublayer_index = 0;
-
-                    // Loop until we find an open spot in a sublayer
-                    loop {
-                        // Get the tile location
-                        let location = (
-                            (tile.px[0] / grid_size) as u32,
-                            (tile.px[1] / grid_size) as u32,
-                        );
-
-                        // Make sure the sub-layer exists
-                        if sublayers.get(sublayer_index).is_none() {
-                            sublayers.push(Default::default());
-                        }
-                        let sublayer = sublayers
-                            .get_mut(sublayer_index)
-                            .expect("Looping logic error");
-
-                        // If the tiles location in the sublayer is empty
-                        if sublayer.get(&location).is_none() {
-                            // Add the tile to the layer
-                            sublayer.insert(
-                                location,
-                                LdtkTilemapTileInfo {
-                                    tile_index: tileset_tile_y * tileset_width_tiles
-                                        + tileset_tile_x,
-                                    flip_bits: if tile.f.x { 1 } else { 0 }
-                                        | if tile.f.y { 2 } else { 0 },
-                                },
-                            );
-
-                            // Break out of the loop
-                            break;
-
-                        // If the tile's location is already taken
-                        } else {
-                            // Increment the sublayer index and try again
-                            sublayer_index += 1;
-                        }
-                    }
+                (key, instance)
+            })
+            .collect();
+    }
+
+    // Count the instances of every entity definition as we go
+    let mut counts = HashMap::<i32, usize>::default();
+
+    layer
+        .entity_instances
+        .iter()
+        .map(|instance| {
+            let index = counts.entry(instance.def_uid).or_insert(0);
+            let key = SpawnedFrom::Entity {
+                layer_def_uid: layer.layer_def_uid,
+                def_uid: instance.def_uid,
+                index: *index,
+            };
+            *index += 1;
+
+            (key, instance)
+        })
+        .collect()
+}
+
+/// Spawn the registered bundle for an entity instance as a child of the level entity, if the
+/// entity has been registered
+fn spawn_ldtk_entity(
+    commands: &mut Commands,
+    ctx: &MapSpawnContext,
+    level_ent: Entity,
+    key: SpawnedFrom,
+    instance: &ldtk::EntityInstance,
+    layer: &ldtk::LayerInstance,
+    layer_index: usize,
+) {
+    if let Some(entity) = ctx.entity_registry.spawn(commands, instance) {
+        commands
+            .entity(entity)
+            .insert(Transform::from_translation(ldtk_px_to_translation(
+                instance.px[0],
+                instance.px[1],
+                ctx.layer_z(layer, layer_index),
+                layer,
+                ctx.config,
+            )))
+            .insert(GlobalTransform::default())
+            .insert(LdtkEntity {
+                instance: instance.clone(),
+            })
+            // Keep track of what the entity was spawned from so that it can be hot reloaded
+            .insert(key);
+
+        // Entities spawned from Y-sorted layers are Y-sorted too
+        if let Some(y_sort) = &ctx.config.y_sort {
+            if y_sort.sorts_layer(&layer.__identifier) {
+                commands.entity(entity).insert(LdtkYSorted::default());
+            }
+        }
+
+        // Add the entity as a child of the level entity
+        commands.entity(level_ent).push_children(&[entity]);
+    }
+}
+
+/// Get the IntGrid values of a layer, if it is an IntGrid layer
+fn layer_int_grid(ctx: &MapSpawnContext, layer: &ldtk::LayerInstance) -> Option<LdtkIntGrid> {
+    if layer.__type == "IntGrid" {
+        Some(LdtkIntGrid::new(
+            layer,
+            ctx.layer_defs[&layer.layer_def_uid],
+            ctx.config.scale,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Get the colliders for an IntGrid layer, if the layer has been registered for them
+fn int_grid_colliders(ctx: &MapSpawnContext, int_grid: &LdtkIntGrid) -> Option<LdtkColliders> {
+    ctx.collider_registry
+        .layers
+        .get(&int_grid.identifier)
+        .map(|values| LdtkColliders::from_int_grid(int_grid, values))
+}
+
+/// Spawn the IntGrid values for a layer as a child of the level entity
+fn spawn_int_grid(
+    commands: &mut Commands,
+    ctx: &MapSpawnContext,
+    level_ent: Entity,
+    int_grid: LdtkIntGrid,
+    layer: &ldtk::LayerInstance,
+    layer_index: usize,
+) {
+    let mut int_grid_commands = commands.spawn();
+    int_grid_commands
+        // Place the IntGrid at the top-left corner of the layer
+        .insert(Transform::from_translation(ldtk_px_to_translation(
+            0,
+            0,
+            ctx.layer_z(layer, layer_index),
+            layer,
+            ctx.config,
+        )))
+        .insert(GlobalTransform::default())
+        .insert(SpawnedFrom::IntGrid {
+            layer_def_uid: layer.layer_def_uid,
+        });
+
+    // Add the colliders for the layer if it has been registered for them
+    if let Some(colliders) = int_grid_colliders(ctx, &int_grid) {
+        int_grid_commands.insert(colliders);
+    }
+
+    let int_grid_ent = int_grid_commands.insert(int_grid).id();
+
+    // Add the entity as a child of the level entity
+    commands.entity(level_ent).push_children(&[int_grid_ent]);
+}
+
+/// Get the tilemap layer components for each sublayer of a layer that should be rendered
+fn layer_tilemaps(
+    ctx: &MapSpawnContext,
+    layer: &ldtk::LayerInstance,
+    layer_index: usize,
+    int_grid: Option<&LdtkIntGrid>,
+) -> Vec<LdtkTilemapLayer> {
+    // Get the tileset texture, the tileset info, and the tiles for each sublayer of this layer
+    let (tileset_texture, tileset_info, sublayer_tiles) = if let Some(uid) = layer.__tileset_def_uid
+    {
+        let (tileset_def, tileset_texture) = &ctx.tilesets[&uid];
+        let (tileset_info, sublayer_tiles) = tileset_layer_tiles(layer, tileset_def);
+
+        (tileset_texture.clone(), tileset_info, sublayer_tiles)
+
+    // Render IntGrid layers without a tileset using the IntGrid value colors, if enabled
+    } else if let (Some(int_grid), Some(palette)) = (
+        int_grid.filter(|_| ctx.config.render_int_grid),
+        ctx.map.int_grid_palettes.get(&layer.layer_def_uid),
+    ) {
+        let layer_def = ctx.layer_defs[&layer.layer_def_uid];
+        let (tileset_info, sublayer_tiles) = int_grid_layer_tiles(layer, layer_def, int_grid);
+
+        (palette.clone(), tileset_info, sublayer_tiles)
+
+    // There is nothing to render for this layer
+    } else {
+        return Vec::new();
+    };
+
+    // For every sublayer
+    sublayer_tiles
+        .into_iter()
+        .enumerate()
+        .map(|(sublayer_index, sublayer_tiles)| LdtkTilemapLayer {
+            // Initialize our map info
+            map_info: LdtkTilemapMapInfo {
+                height: layer.__c_hei as u32,
+                width: layer.__c_wid as u32,
+                layer_index: layer_index as u32,
+                sublayer_index: sublayer_index as u32,
+                z: ctx.layer_z(layer, layer_index)
+                    + sublayer_index as f32 * ctx.config.sublayer_z_step,
+                center_map: if ctx.config.center_map { 1 } else { 0 },
+                grid_size: layer.__grid_size as u32,
+                offset_x: layer.__px_total_offset_x,
+                offset_y: layer.__px_total_offset_y,
+                layer_width: layer.__c_wid as u32,
+                layer_height: layer.__c_hei as u32,
+            },
+            scale: ctx.config.scale,
+            texture: tileset_texture.clone(),
+            tiles: sublayer_tiles,
+            tileset_info,
+            display_info: LdtkTilemapDisplayInfo {
+                opacity: layer.__opacity,
+                visible: if layer.visible { 1 } else { 0 },
+                ..Default::default()
+            },
+        })
+        .collect()
+}
+
+/// Get the tilemap layer components that should be spawned for a layer, along with the keys that
+/// identify them
+///
+/// The Y-sorted tiles of each sublayer are split out into rows that are spawned separately, and
+/// the rest of the sublayer is split into chunks if the map has a chunk size.
+fn keyed_layer_tilemaps(
+    ctx: &MapSpawnContext,
+    layer: &ldtk::LayerInstance,
+    level_index: usize,
+    layer_index: usize,
+    int_grid: Option<&LdtkIntGrid>,
+) -> Vec<(SpawnedFrom, LdtkTilemapLayer)> {
+    let mut tilemaps = Vec::new();
+
+    for (sublayer_index, mut tilemap_layer) in layer_tilemaps(ctx, layer, layer_index, int_grid)
+        .into_iter()
+        .enumerate()
+    {
+        if let Some(y_sort) = &ctx.config.y_sort {
+            tilemaps.extend(y_sort_rows(
+                ctx,
+                y_sort,
+                layer,
+                level_index,
+                &mut tilemap_layer,
+            ));
+        }
+
+        // Split what is left of the sublayer into chunks if chunking is enabled
+        match ctx.config.chunk_size.filter(|&x| x > 0) {
+            Some(chunk_size) => tilemaps.extend(tile_chunks(layer, chunk_size, &tilemap_layer)),
+            None => tilemaps.push((
+                SpawnedFrom::Tiles {
+                    layer_def_uid: layer.layer_def_uid,
+                    sublayer_index,
+                },
+                tilemap_layer,
+            )),
+        }
+    }
+
+    tilemaps
+}
+
+/// Move the Y-sorted tiles of a rendered sublayer into a separate tilemap layer for every row that
+/// has Y-sorted tiles, placing each row on the Z axis according to its Y coordinate
+fn y_sort_rows(
+    ctx: &MapSpawnContext,
+    y_sort: &LdtkYSort,
+    layer: &ldtk::LayerInstance,
+    level_index: usize,
+    tilemap_layer: &mut LdtkTilemapLayer,
+) -> Vec<(SpawnedFrom, LdtkTilemapLayer)> {
+    let sorts_layer = y_sort.sorts_layer(&layer.__identifier);
+    let tileset_meta = layer
+        .__tileset_def_uid
+        .and_then(|uid| ctx.map.tileset_meta.get(&uid));
+    let is_sorted = |tile: &LdtkTilemapTileInfo| {
+        !tile.is_empty()
+            && (sorts_layer
+                || tileset_meta
+                    .map(|meta| y_sort.sorts_tile(meta, tile.tile_index))
+                    .unwrap_or(false))
+    };
+
+    let map_info = tilemap_layer.map_info;
+    let sublayer_index = map_info.sublayer_index as usize;
+    let width = map_info.width as usize;
+    let grid_size = map_info.grid_size as i32;
+    let origin = level_origin(&ctx.map.project, level_index, ctx.config);
+    let geometry =
+        LdtkLayerGeometry::from_layer_instance(&GlobalTransform::identity(), layer, ctx.config);
+
+    let mut rows = Vec::new();
+    for row in 0..map_info.height {
+        let row_tiles = &mut tilemap_layer.tiles[row as usize * width..(row as usize + 1) * width];
+        if !row_tiles.iter().any(&is_sorted) {
+            continue;
+        }
+
+        // Move the Y-sorted tiles out of the layer
+        let mut tiles = vec![LdtkTilemapTileInfo::EMPTY; width];
+        for (i, tile) in row_tiles.iter_mut().enumerate() {
+            if is_sorted(tile) {
+                tiles[i] = *tile;
+                *tile = LdtkTilemapTileInfo::EMPTY;
+            }
+        }
+
+        // Sort the row by the Y coordinate of its bottom edge relative to the map
+        let bottom = origin.y
+            + geometry
+                .ldtk_px_to_local(Vec2::new(0.0, ((row + 1) as i32 * grid_size) as f32))
+                .y;
+
+        let row_layer = LdtkTilemapLayer {
+            map_info: LdtkTilemapMapInfo {
+                z: y_sort.z(bottom) + sublayer_index as f32 * ctx.config.sublayer_z_step,
+                ..region_map_info(&map_info, 0, row, map_info.width, 1)
+            },
+            scale: tilemap_layer.scale,
+            tileset_info: tilemap_layer.tileset_info,
+            texture: tilemap_layer.texture.clone(),
+            tiles,
+            display_info: tilemap_layer.display_info,
+        };
+
+        rows.push((
+            SpawnedFrom::YSortedRow {
+                layer_def_uid: layer.layer_def_uid,
+                sublayer_index,
+                row,
+            },
+            row_layer,
+        ));
+    }
+
+    rows
+}
+
+/// Split a rendered sublayer into chunks of `chunk_size` by `chunk_size` cells, along with the keys
+/// that identify them
+///
+/// The chunks on the right and bottom edges are smaller if the layer size isn't a multiple of the
+/// chunk size.
+fn tile_chunks(
+    layer: &ldtk::LayerInstance,
+    chunk_size: u32,
+    tilemap_layer: &LdtkTilemapLayer,
+) -> Vec<(SpawnedFrom, LdtkTilemapLayer)> {
+    let map_info = tilemap_layer.map_info;
+
+    let mut chunks = Vec::new();
+    for y in (0..map_info.height).step_by(chunk_size as usize) {
+        for x in (0..map_info.width).step_by(chunk_size as usize) {
+            let chunk_info = region_map_info(
+                &map_info,
+                x,
+                y,
+                chunk_size.min(map_info.width - x),
+                chunk_size.min(map_info.height - y),
+            );
+
+            // Copy the tiles of the chunk, in the same order as the tiles of the layer
+            let mut tiles = Vec::with_capacity((chunk_info.width * chunk_info.height) as usize);
+            for chunk_y in 0..chunk_info.height {
+                for chunk_x in (0..chunk_info.width).rev() {
+                    let i = tilemap_layer
+                        .tile_buffer_index(x + chunk_x, y + chunk_y)
+                        .expect("Chunk is inside of the layer");
+                    tiles.push(tilemap_layer.tiles[i]);
+                }
+            }
+
+            chunks.push((
+                SpawnedFrom::TileChunk {
+                    layer_def_uid: layer.layer_def_uid,
+                    sublayer_index: map_info.sublayer_index as usize,
+                    origin: (x, y),
+                },
+                LdtkTilemapLayer {
+                    map_info: chunk_info,
+                    scale: tilemap_layer.scale,
+                    tileset_info: tilemap_layer.tileset_info,
+                    texture: tilemap_layer.texture.clone(),
+                    tiles,
+                    display_info: tilemap_layer.display_info,
+                },
+            ));
+        }
+    }
+
+    chunks
+}
+
+/// Get the map info for a rectangle of cells of a rendered sublayer, where `x` and `y` are the
+/// coordinates of the top-left cell of the rectangle
+///
+/// The rectangle is offset to its place in the layer, and keeps the size of the whole layer so
+/// that it is centered in the same place as the rest of the layer.
+fn region_map_info(
+    map_info: &LdtkTilemapMapInfo,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> LdtkTilemapMapInfo {
+    let grid_size = map_info.grid_size as i32;
+    let layer_size = map_info.layer_size();
+
+    LdtkTilemapMapInfo {
+        width,
+        height,
+        offset_x: map_info.offset_x + x as i32 * grid_size,
+        offset_y: map_info.offset_y + y as i32 * grid_size,
+        layer_width: layer_size.x,
+        layer_height: layer_size.y,
+        ..*map_info
+    }
+}
+
+/// Spawn a rendered sublayer as a child of the level entity
+fn spawn_tilemap_layer(
+    commands: &mut Commands,
+    ctx: &MapSpawnContext,
+    level_ent: Entity,
+    key: SpawnedFrom,
+    layer: &ldtk::LayerInstance,
+    tilemap_layer: LdtkTilemapLayer,
+) {
+    let tile_layer = LdtkTileLayer {
+        identifier: layer.__identifier.clone(),
+        sublayer_index: tilemap_layer.map_info.sublayer_index as usize,
+        row: match key {
+            SpawnedFrom::YSortedRow { row, .. } => Some(row),
+            _ => None,
+        },
+        chunk_origin: match key {
+            SpawnedFrom::TileChunk { origin, .. } => Some(origin),
+            _ => None,
+        },
+    };
+    let animated_tiles = layer_animated_tiles(ctx, layer, &tilemap_layer);
+
+    // Spawn the layer into the world
+    let layer_ent = commands
+        // Use the default sprite bundle with our custom render pipeline
+        .spawn_bundle(SpriteBundle {
+            render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
+                LDTK_TILEMAP_PIPELINE_HANDLE.typed(),
+            )]),
+            ..Default::default()
+        })
+        // Add our material which the shaders will use to render the map
+        .insert(tilemap_layer)
+        .insert(tile_layer)
+        // Keep track of what the layer was spawned from so that it can be hot reloaded
+        .insert(key)
+        .id();
+
+    if let Some(animated_tiles) = animated_tiles {
+        commands.entity(layer_ent).insert(animated_tiles);
+    }
+
+    // Add the entity as a child of the level entity
+    commands.entity(level_ent).push_children(&[layer_ent]);
+}
+
+/// Get the animated tiles of a rendered sublayer, if it has any
+fn layer_animated_tiles(
+    ctx: &MapSpawnContext,
+    layer: &ldtk::LayerInstance,
+    tilemap_layer: &LdtkTilemapLayer,
+) -> Option<LdtkAnimatedTiles> {
+    // IntGrid layers rendered with their value colors don't have a tileset to animate
+    let tileset_uid = layer.__tileset_def_uid?;
+    let animations = ctx.map.tile_animations.get(&tileset_uid)?;
+
+    LdtkAnimatedTiles::new(tileset_uid, tilemap_layer, animations)
+}
+
+/// Get the tileset info and the tiles for each sublayer of a tile layer
+fn tileset_layer_tiles(
+    layer: &ldtk::LayerInstance,
+    tileset_info: &ldtk::TilesetDef,
+) -> (LdtkTilemapTilesetInfo, Vec<Vec<LdtkTilemapTileInfo>>) {
+    // Create a list of all the tiles in the layer
+    let tiles = if !layer.auto_layer_tiles.is_empty() {
+        &layer.auto_layer_tiles
+    } else {
+        &layer.grid_tiles
+    };
+
+    // Create a vector of "sublayers", each of which has a mapping of the (x, y)
+    // coordinate of the tile to the tile information for that location. Because LDtk's
+    // auto-mapped tiles support having multiple tiles in the same cell, we may need
+    // more than one sublayer for the overlayed tiles.
+    let mut sublayers: Vec<HashMap<(u32, u32), LdtkTilemapTileInfo>> = Vec::new();
+
+    // The size of the grid cells in this layer
+    let grid_size = layer.__grid_size;
+
+    // The size of the tiles in the tileset, and the distance between the start of one
+    // tile and the start of the next
+    let tile_size = tileset_info.tile_grid_size;
+    let tile_stride = tile_size + tileset_info.spacing;
+
+    // The width and height of the tileset in tiles
+    let tileset_width_tiles = tileset_tiles_count(tileset_info.px_wid, tile_size, tileset_info);
+    let tileset_height_tiles = tileset_tiles_count(tileset_info.px_hei, tile_size, tileset_info);
+
+    // For every tile in the layer
+    for tile in tiles {
+        // Get the x and y position of the tile in the tileset
+        let tileset_tile_x = ((tile.src[0] - tileset_info.padding) / tile_stride) as u32;
+        let tileset_tile_y = ((tile.src[1] - tileset_info.padding) / tile_stride) as u32;
+
+        // Add the tile and it's info to the (x, y) position in our tiles HashMap, and
+        // add it to the list of tiles in that square
+        let mut sublayer_index = 0;
+
+        // Loop until we find an open spot in a sublayer
+        loop {
+            // Get the tile location
+            let location = (
+                (tile.px[0] / grid_size) as u32,
+                (tile.px[1] / grid_size) as u32,
+            );
+
+            // Make sure the sub-layer exists
+            if sublayers.get(sublayer_index).is_none() {
+                sublayers.push(Default::default());
+            }
+            let sublayer = sublayers
+                .get_mut(sublayer_index)
+                .expect("Looping logic error");
+
+            // If the tiles location in the sublayer is empty
+            if sublayer.get(&location).is_none() {
+                // Add the tile to the layer
+                sublayer.insert(
+                    location,
+                    LdtkTilemapTileInfo::new(
+                        tileset_tile_y * tileset_width_tiles + tileset_tile_x,
+                        tile.f.clone(),
+                    ),
+                );
+
+                // Break out of the loop
+                break;
+
+            // If the tile's location is already taken
+            } else {
+                // Increment the sublayer index and try again
+                sublayer_index += 1;
+            }
+        }
+    }
+
+    // Layers without any tiles still get an empty sublayer, so that tiles can be added to them at
+    // runtime
+    if sublayers.is_empty() {
+        sublayers.push(Default::default());
+    }
+
+    // Go through our sublayers and convert each one to a 1D vector of all of the tiles'
+    // information.
+    let mut sublayer_tiles: Vec<Vec<LdtkTilemapTileInfo>> =
+        vec![Default::default(); sublayers.len()];
+
+    // For every sublayer and it's corresponding 1D sublayer_tiles
+    for (sublayer_tiles, sublayer) in sublayer_tiles.iter_mut().zip(sublayers) {
+        // Loop through all the X and Y coords
+        for y in 0..layer.__c_hei as u32 {
+            for x in (0..layer.__c_wid as u32).rev() {
+                // Add a the tile to the tile list
+                sublayer_tiles.push(
+                    sublayer
+                        .get(&(x, y))
+                        .copied()
+                        // Or add a blank tile if one is not at these coordinates
+                        .unwrap_or(LdtkTilemapTileInfo::EMPTY),
+                )
+            }
+        }
+    }
+
+    (
+        LdtkTilemapTilesetInfo {
+            height: tileset_height_tiles,
+            width: tileset_width_tiles,
+            grid_size: tile_size as u32,
+            spacing: tileset_info.spacing as u32,
+            padding: tileset_info.padding as u32,
+        },
+        sublayer_tiles,
+    )
+}
+
+/// Get the tileset info and the tiles for an IntGrid layer that is rendered using the IntGrid
+/// value colors
+///
+/// The layer is rendered with a palette texture that is one pixel tall and has one pixel for each
+/// of the IntGrid values, in the same order as the layer definition's `int_grid_values`.
+fn int_grid_layer_tiles(
+    layer: &ldtk::LayerInstance,
+    layer_def: &ldtk::LayerDef,
+    int_grid: &LdtkIntGrid,
+) -> (LdtkTilemapTilesetInfo, Vec<Vec<LdtkTilemapTileInfo>>) {
+    // Map the IntGrid values to the index of their color in the palette
+    let palette_indexes = layer_def
+        .int_grid_values
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.value, i as u32))
+        .collect::<HashMap<_, _>>();
+
+    // Add every cell in the layer to the tile list, going from right to left to match the tile
+    // layers
+    let mut tiles = Vec::with_capacity(int_grid.values.len());
+    for y in 0..layer.__c_hei as u32 {
+        for x in (0..layer.__c_wid as u32).rev() {
+            tiles.push(LdtkTilemapTileInfo {
+                tile_index: int_grid
+                    .get(x, y)
+                    .and_then(|value| palette_indexes.get(&value))
+                    .copied()
+                    // Empty cells and unknown values get a blank tile
+                    .unwrap_or(u32::MAX),
+                flip_bits: 0,
+            });
+        }
+    }
+
+    (
+        LdtkTilemapTilesetInfo {
+            width: layer_def.int_grid_values.len() as u32,
+            height: 1,
+            grid_size: 1,
+            spacing: 0,
+            padding: 0,
+        },
+        vec![tiles],
+    )
+}
+
+/// Convert a pixel position in an LDtk layer to a translation relative to the map entity
+///
+/// This matches the placement of the map layers in the vertex shader: LDtk's +y is down, so it is
+/// flipped, the layer offset is applied, and everything is multiplied by the map scale.
+fn ldtk_px_to_translation(
+    x: i32,
+    y: i32,
+    z: f32,
+    layer: &ldtk::LayerInstance,
+    config: &LdtkMapConfig,
+) -> Vec3 {
+    LdtkLayerGeometry::from_layer_instance(&GlobalTransform::identity(), layer, config)
+        .ldtk_px_to_local(Vec2::new(x as f32, y as f32))
+        .extend(z)
+}
+
+/// Parse an LDtk hex color string, such as `#1D2135`
+fn parse_color(color: &str) -> Result<Color, LdtkMapSpawnError> {
+    color
+        .strip_prefix('#')
+        .and_then(|hex| Color::hex(hex).ok())
+        .ok_or_else(|| LdtkMapSpawnError::InvalidColor(color.into()))
+}
+
+/// Get the number of tiles that fit along one side of a tileset image of `px_size` pixels, taking
+/// into account the tileset's padding and spacing
+fn tileset_tiles_count(px_size: i32, tile_size: i32, tileset: &ldtk::TilesetDef) -> u32 {
+    // Remove the padding from both sides of the image, then count how many tiles, each followed by
+    // spacing, will fit. We add one spacing to the available space because the last tile isn't
+    // followed by any spacing.
+    ((px_size - tileset.padding * 2 + tileset.spacing) / (tile_size + tileset.spacing)) as u32
+}
+
+/// This system re-spawns maps that have had their [`LdtkMapConfig`] changed after they were
+/// spawned, such as when a different level has been selected
+#[allow(clippy::type_complexity)]
+fn respawn_changed_maps(
+    mut commands: Commands,
+    changed_maps: Query<(Entity, &LdtkMapConfig), (With<LdtkMapHasLoaded>, Changed<LdtkMapConfig>)>,
+    levels: Query<(Entity, &Parent), With<LdtkMapLevel>>,
+    mut map_events: EventWriter<LdtkMapEvent>,
+) {
+    for (map_ent, config) in changed_maps.iter() {
+        // Despawn the spawned levels, along with their layers and entities
+        for (level_ent, parent) in levels.iter() {
+            if parent.0 == map_ent {
+                commands.entity(level_ent).despawn_recursive();
+            }
+        }
+
+        map_events.send(LdtkMapEvent::Despawned {
+            map: map_ent,
+            levels: config.levels.clone(),
+        });
+
+        // Remove the `LdtkMapHasLoaded` component so that the map will be spawned again by the
+        // `process_ldtk_maps` system.
+        commands.entity(map_ent).remove::<LdtkMapHasLoaded>();
+    }
+}
+
+/// This system spawns and despawns the levels of streamed maps around the [`LdtkLevelStreamer`]s
+///
+/// Levels that can't be spawned are remembered, so that their errors are only reported once, until
+/// the map asset changes.
+#[allow(clippy::too_many_arguments)]
+fn stream_levels(
+    mut commands: Commands,
+    mut failed_levels: Local<HashSet<(Entity, Option<usize>)>>,
+    mut map_asset_events: EventReader<MapEvent>,
+    streamers: Query<(&LdtkLevelStreamer, &GlobalTransform)>,
+    maps: Query<(&Handle<LdtkMap>, &LdtkMapConfig, &GlobalTransform), With<LdtkMapHasLoaded>>,
+    levels: Query<(Entity, &LdtkMapLevel, &Parent)>,
+    map_assets: Res<Assets<LdtkMap>>,
+    entity_registry: Res<LdtkEntityRegistry>,
+    collider_registry: Res<LdtkColliderRegistry>,
+    mut spawned_events: EventWriter<LevelSpawned>,
+    mut despawned_events: EventWriter<LevelDespawned>,
+    mut error_events: EventWriter<LdtkMapError>,
+) {
+    // Try the levels that failed again when a map asset changes
+    if map_asset_events.iter().count() > 0 {
+        failed_levels.clear();
+    }
+
+    // Collect the indexes of the levels that should be spawned for every map
+    let mut wanted_levels: HashMap<Entity, HashSet<usize>> = HashMap::default();
+
+    for (streamer, streamer_transform) in streamers.iter() {
+        // Get the streamed map and its asset, if it has been spawned
+        let (map_handle, config, map_transform) = match maps.get(streamer.map) {
+            Ok(map) if map.1.levels == LevelSelection::Streamed => map,
+            _ => continue,
+        };
+        let project = match map_assets.get(map_handle) {
+            Some(map) => &map.project,
+            None => continue,
+        };
+
+        // Get the position of the streamer relative to the map
+        let position = map_transform
+            .compute_matrix()
+            .inverse()
+            .transform_point3(streamer_transform.translation)
+            .truncate();
+
+        let bounds = (0..project.levels.len())
+            .map(|i| level_bounds(project, i, config))
+            .collect::<Vec<_>>();
+        let uid_indexes = project
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.uid, i))
+            .collect::<HashMap<_, _>>();
+
+        // Start with the level that the streamer is in, or the levels within the radius if the
+        // streamer isn't inside of a level
+        let mut to_visit = match bounds.iter().position(|x| x.contains(position)) {
+            Some(current) => vec![current],
+            None => (0..bounds.len())
+                .filter(|&i| bounds[i].distance(position) <= streamer.radius)
+                .collect(),
+        };
+
+        // Walk through the neighbours of the levels that are within the radius
+        let wanted = wanted_levels.entry(streamer.map).or_default();
+        while let Some(level_index) = to_visit.pop() {
+            if !wanted.insert(level_index) {
+                continue;
+            }
+
+            to_visit.extend(
+                project.levels[level_index]
+                    .__neighbours
+                    .iter()
+                    .filter_map(|x| uid_indexes.get(&x.level_uid).copied())
+                    .filter(|&i| bounds[i].distance(position) <= streamer.radius),
+            );
+        }
+    }
+
+    // Despawn the levels that are no longer wanted
+    for (level_ent, level, parent) in levels.iter() {
+        if let Some(wanted) = wanted_levels.get_mut(&parent.0) {
+            // Remove spawned levels from the wanted set so that they aren't spawned again below
+            if !wanted.remove(&level.index) {
+                commands.entity(level_ent).despawn_recursive();
+                despawned_events.send(LevelDespawned {
+                    map: parent.0,
+                    uid: level.uid,
+                    identifier: level.identifier.clone(),
+                    iid: level.iid.clone(),
+                });
+            }
+        }
+    }
+
+    // Spawn the wanted levels that haven't been spawned yet
+    for (map_ent, wanted) in wanted_levels {
+        if wanted.is_empty() {
+            continue;
+        }
+
+        // Skip maps that can't be spawned at all
+        if failed_levels.contains(&(map_ent, None)) {
+            continue;
+        }
+
+        let (map_handle, config, _) = match maps.get(map_ent) {
+            Ok(map) => map,
+            Err(_) => continue,
+        };
+        let map = match map_assets.get(map_handle) {
+            Some(map) => map,
+            None => continue,
+        };
+
+        let ctx = match MapSpawnContext::new(map, config, &entity_registry, &collider_registry) {
+            Ok(ctx) => ctx,
+            Err(error) => {
+                failed_levels.insert((map_ent, None));
+                error_events.send(LdtkMapError {
+                    entity: map_ent,
+                    error,
+                });
+                continue;
+            }
+        };
+
+        // Validate all of the wanted levels before spawning any of them, reporting every level
+        // that fails once
+        let mut valid = true;
+        for &level_index in &wanted {
+            if let Err(error) = ctx.validate_level(level_index) {
+                valid = false;
+
+                if failed_levels.insert((map_ent, Some(level_index))) {
+                    error_events.send(LdtkMapError {
+                        entity: map_ent,
+                        error,
+                    });
+                }
+            }
+        }
+        if !valid {
+            continue;
+        }
+
+        for level_index in wanted {
+            let level = &map.project.levels[level_index];
+            spawned_events.send(LevelSpawned {
+                map: map_ent,
+                level: ctx.spawn_level(&mut commands, map_ent, level_index),
+                uid: level.uid,
+                identifier: level.identifier.clone(),
+                iid: map.level_iids.get(&level.uid).cloned(),
+            });
+        }
+    }
+}
+
+/// A level that a tracked entity is inside of
+struct TrackedLevel {
+    map: Entity,
+    level: Entity,
+    uid: i32,
+    identifier: String,
+    iid: Option<String>,
+}
+
+/// This system sends events when entities with an [`LdtkLevelTracker`] move between levels
+fn track_levels(
+    mut current_levels: Local<HashMap<Entity, Vec<TrackedLevel>>>,
+    trackers: Query<(Entity, &GlobalTransform), With<LdtkLevelTracker>>,
+    levels: Query<(Entity, &LdtkMapLevel, &Parent)>,
+    maps: Query<&GlobalTransform, With<LdtkMapConfig>>,
+    mut entered_events: EventWriter<LevelEntered>,
+    mut exited_events: EventWriter<LevelExited>,
+) {
+    // Exit the levels of any trackers that have been removed or despawned
+    current_levels.retain(|&entity, tracked_levels| {
+        let exists = trackers.get(entity).is_ok();
+        if !exists {
+            for level in tracked_levels.drain(..) {
+                exited_events.send(LevelExited {
+                    entity,
+                    map: level.map,
+                    uid: level.uid,
+                    identifier: level.identifier,
+                    iid: level.iid,
+                });
+            }
+        }
+        exists
+    });
+
+    for (entity, transform) in trackers.iter() {
+        // Find all of the spawned levels that the tracked entity is inside of
+        let inside = levels
+            .iter()
+            .filter(|(_, level, parent)| {
+                maps.get(parent.0)
+                    .map(|map_transform| {
+                        let position = map_transform
+                            .compute_matrix()
+                            .inverse()
+                            .transform_point3(transform.translation);
+                        level.bounds.contains(position.truncate())
+                    })
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+
+        let tracked_levels = current_levels.entry(entity).or_default();
+
+        // Exit the levels that the entity is no longer inside of
+        tracked_levels.retain(|tracked| {
+            let still_inside = inside
+                .iter()
+                .any(|(level_ent, ..)| *level_ent == tracked.level);
+            if !still_inside {
+                exited_events.send(LevelExited {
+                    entity,
+                    map: tracked.map,
+                    uid: tracked.uid,
+                    identifier: tracked.identifier.clone(),
+                    iid: tracked.iid.clone(),
+                });
+            }
+            still_inside
+        });
+
+        // Enter the levels that the entity wasn't inside of before
+        for (level_ent, level, parent) in inside {
+            if tracked_levels.iter().any(|x| x.level == level_ent) {
+                continue;
+            }
+
+            entered_events.send(LevelEntered {
+                entity,
+                map: parent.0,
+                level: level_ent,
+                uid: level.uid,
+                identifier: level.identifier.clone(),
+                iid: level.iid.clone(),
+            });
+            tracked_levels.push(TrackedLevel {
+                map: parent.0,
+                level: level_ent,
+                uid: level.uid,
+                identifier: level.identifier.clone(),
+                iid: level.iid.clone(),
+            });
+        }
+    }
+}
+
+/// The spawned levels of the maps, along with the layers that were spawned for them
+type SpawnedLevels<'a> = Query<
+    'a,
+    (
+        Entity,
+        &'static LdtkMapLevel,
+        &'static SpawnedLevelLayers,
+        &'static Parent,
+        Option<&'static Children>,
+    ),
+>;
+
+/// The spawned levels of the maps and the entities spawned for them, used to update the maps in
+/// place when they are hot reloaded
+struct SpawnedMapItems<'a> {
+    levels: SpawnedLevels<'a>,
+    items: Query<'a, &'static SpawnedFrom>,
+    tilemap_layers: Query<'a, &'static mut LdtkTilemapLayer>,
+    int_grids: Query<'a, &'static mut LdtkIntGrid>,
+    ldtk_entities: Query<'a, &'static mut LdtkEntity>,
+    transforms: Query<'a, &'static mut Transform, With<SpawnedFrom>>,
+}
+
+/// Update the spawned levels of a map in place after the map asset has been modified, returning
+/// `false` if the map has changed too much to be updated and has to be re-spawned instead
+///
+/// Only the layers that have changed are updated, and entities that haven't changed in the map are
+/// left alone, so that any gameplay state on them survives the reload.
+fn reload_map(
+    commands: &mut Commands,
+    ctx: &MapSpawnContext,
+    map_ent: Entity,
+    items: &mut SpawnedMapItems,
+) -> bool {
+    let project = &ctx.map.project;
+
+    // Get the levels that were spawned for this map
+    let spawned_levels = items
+        .levels
+        .iter()
+        .filter(|(_, _, _, parent, _)| parent.0 == map_ent)
+        .map(|(level_ent, level, layers, _, children)| {
+            (
+                level_ent,
+                level.clone(),
+                layers.0.clone(),
+                children.map(|x| x.to_vec()).unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // The levels of streamed maps are spawned and despawned by the streamers, but for any other map
+    // the selected levels must be the ones that were spawned
+    if ctx.config.levels != LevelSelection::Streamed {
+        let mut selected_uids = match ctx.config.levels.select(ctx.map) {
+            Ok(indexes) => indexes
+                .iter()
+                .map(|&i| project.levels[i].uid)
+                .collect::<Vec<_>>(),
+            Err(_) => return false,
+        };
+        let mut spawned_uids = spawned_levels
+            .iter()
+            .map(|(_, level, ..)| level.uid)
+            .collect::<Vec<_>>();
+
+        selected_uids.sort_unstable();
+        spawned_uids.sort_unstable();
+        if selected_uids != spawned_uids {
+            return false;
+        }
+    }
+
+    // Make sure that every spawned level is still in the same place and has the same layers
+    let mut level_indexes = Vec::with_capacity(spawned_levels.len());
+    for (_, spawned_level, layer_sources, _) in &spawned_levels {
+        let level_index = match project
+            .levels
+            .iter()
+            .position(|x| x.uid == spawned_level.uid)
+        {
+            Some(index) => index,
+            None => return false,
+        };
+
+        if ctx.validate_level(level_index).is_err()
+            || level_bounds(project, level_index, ctx.config) != spawned_level.bounds
+        {
+            return false;
+        }
+
+        let layers = project.levels[level_index]
+            .layer_instances
+            .as_ref()
+            .expect("Missing level layers");
+        if !layers
+            .iter()
+            .map(|x| x.layer_def_uid)
+            .eq(layer_sources.iter().map(|x| x.layer_def_uid))
+        {
+            return false;
+        }
+
+        level_indexes.push(level_index);
+    }
+
+    for ((level_ent, _, layer_sources, children), level_index) in
+        spawned_levels.into_iter().zip(level_indexes)
+    {
+        // Collect the entities that were spawned from the level data, ignoring any other children
+        // that may have been added to the level
+        let mut spawned = children
+            .into_iter()
+            .filter_map(|child| items.items.get(child).ok().map(|key| (key.clone(), child)))
+            .collect::<HashMap<_, _>>();
+
+        let layers = project.levels[level_index]
+            .layer_instances
+            .as_ref()
+            .expect("Missing level layers");
+        let new_layer_sources = layers
+            .iter()
+            .map(|x| LayerSource::new(ctx, level_index, x))
+            .collect::<Vec<_>>();
+        for (layer_index, ((layer, old_source), new_source)) in layers
+            .iter()
+            .zip(&layer_sources)
+            .zip(&new_layer_sources)
+            .rev()
+            .enumerate()
+        {
+            // Leave the layers that haven't changed in the map alone, so that changes made to them
+            // at runtime, like edited tiles, are kept
+            if old_source == new_source {
+                spawned.retain(|key, _| key.layer_def_uid() != layer.layer_def_uid);
+                continue;
+            }
+
+            reload_layer(
+                commands,
+                ctx,
+                level_ent,
+                level_index,
+                layer,
+                layer_index,
+                &mut spawned,
+                items,
+            );
+        }
+
+        // Despawn anything that is no longer in the level
+        for entity in spawned.values() {
+            commands.entity(*entity).despawn_recursive();
+        }
+
+        commands
+            .entity(level_ent)
+            .insert(SpawnedLevelLayers(new_layer_sources));
+    }
+
+    true
+}
+
+/// Update the entities spawned for a layer in place, spawning anything that is new
+///
+/// Every entity that is still in the layer is removed from `spawned`.
+#[allow(clippy::too_many_arguments)]
+fn reload_layer(
+    commands: &mut Commands,
+    ctx: &MapSpawnContext,
+    level_ent: Entity,
+    level_index: usize,
+    layer: &ldtk::LayerInstance,
+    layer_index: usize,
+    spawned: &mut HashMap<SpawnedFrom, Entity>,
+    items: &mut SpawnedMapItems,
+) {
+    // Update the entities in the layer
+    for (key, instance) in layer_entity_instances(layer, ctx.entity_iids(level_index, layer)) {
+        let entity = match spawned.remove(&key) {
+            Some(entity) => entity,
+            None => {
+                spawn_ldtk_entity(commands, ctx, level_ent, key, instance, layer, layer_index);
+                continue;
+            }
+        };
+
+        if let Ok(mut ldtk_entity) = items.ldtk_entities.get_mut(entity) {
+            // Only touch the entity if it was changed in the map, so that gameplay changes to it
+            // are kept
+            if serde_json::to_value(&ldtk_entity.instance).ok()
+                == serde_json::to_value(instance).ok()
+            {
+                continue;
+            }
+
+            // Only move the entity if it was moved in the map
+            if ldtk_entity.instance.px != instance.px {
+                if let Ok(mut transform) = items.transforms.get_mut(entity) {
+                    transform.translation = ldtk_px_to_translation(
+                        instance.px[0],
+                        instance.px[1],
+                        ctx.layer_z(layer, layer_index),
+                        layer,
+                        ctx.config,
+                    );
                 }
+            }
+
+            ldtk_entity.instance = instance.clone();
+        }
+    }
+
+    // Update the IntGrid values
+    let int_grid = layer_int_grid(ctx, layer);
+    if let Some(int_grid) = &int_grid {
+        let key = SpawnedFrom::IntGrid {
+            layer_def_uid: layer.layer_def_uid,
+        };
 
-                // Go through our sublayers and convert each one to a 1D vector of all of the tiles'
-                // information.
-                let mut sublayer_tiles: Vec<Vec<LdtkTilemapTileInfo>> =
-                    vec![Default::default(); sublayers.len()];
-
-                // For every sublayer and it's corresponding 1D sublayer_tiles
-                for (sublayer_tiles, sublayer) in sublayer_tiles.iter_mut().zip(sublayers) {
-                    // Loop through all the X and Y coords
-                    for y in 0..layer.__c_hei as u32 {
-                        for x in (0..layer.__c_wid as u32).rev() {
-                            // Add a the tile to the tile list
-                            sublayer_tiles.push(
-                                sublayer
-                                    .get(&(x, y))
-                                    .map(Clone::clone)
-                                    // Or add a blank tile if one is not at these coordinates
-                                    .unwrap_or(LdtkTilemapTileInfo {
-                                        flip_bits: 0,
-                                        tile_index: u32::MAX,
-                                    }),
-                            )
+        match spawned.remove(&key) {
+            Some(entity) => {
+                if let Ok(mut current) = items.int_grids.get_mut(entity) {
+                    if *current != *int_grid {
+                        *current = int_grid.clone();
+
+                        if let Some(colliders) = int_grid_colliders(ctx, int_grid) {
+                            commands.entity(entity).insert(colliders);
                         }
                     }
                 }
 
-                // For every sublayer
-                for (sublayer_index, sublayer_tiles) in sublayer_tiles.into_iter().enumerate() {
-                    // Initialize our map info
-                    let map_info = LdtkTilemapMapInfo {
-                        height: (level.px_hei / map.project.default_grid_size) as u32,
-                        width: (level.px_wid / map.project.default_grid_size) as u32,
-                        layer_index: z as u32,
-                        sublayer_index: sublayer_index as u32,
-                        center_map: if config.center_map { 1 } else { 0 },
-                    };
-
-                    // Initialize our tileset info
-                    let tileset_info = LdtkTilemapTilesetInfo {
-                        height: (tileset_info.px_hei / map.project.default_grid_size) as u32,
-                        width: (tileset_info.px_wid / map.project.default_grid_size) as u32,
-                        grid_size: map.project.default_grid_size as u32,
-                    };
-
-                    // Spawn the layer into the world
-                    let layer = commands
-                        // Use the default sprite bundle with our custom render pipeline
-                        .spawn_bundle(SpriteBundle {
-                            render_pipelines: RenderPipelines::from_pipelines(vec![
-                                RenderPipeline::new(LDTK_TILEMAP_PIPELINE_HANDLE.typed()),
-                            ]),
-                            ..Default::default()
-                        })
-                        // Add our material which the shaders will use to render the map
-                        .insert(LdtkTilemapLayer {
-                            map_info,
-                            scale: config.scale,
-                            texture: tileset_texture.clone(),
-                            tiles: sublayer_tiles,
-                            tileset_info,
-                        })
-                        // Add the `Handle<LdtkMap>` so that we will be able to hot reload this layer if
-                        // the map changes.
-                        .insert(LayerMapHandle(map_handle.clone()))
-                        .id();
-
-                    // Add the entity as a child of the LDtk map entity
-                    commands.entity(ent).push_children(&[layer]);
+                // The layer offset may have changed
+                let translation = ldtk_px_to_translation(
+                    0,
+                    0,
+                    ctx.layer_z(layer, layer_index),
+                    layer,
+                    ctx.config,
+                );
+                if let Ok(mut transform) = items.transforms.get_mut(entity) {
+                    if transform.translation != translation {
+                        transform.translation = translation;
+                    }
                 }
             }
+            None => spawn_int_grid(
+                commands,
+                ctx,
+                level_ent,
+                int_grid.clone(),
+                layer,
+                layer_index,
+            ),
+        }
+    }
 
-            // Mark the map as having been loaded so that we don't process it again
-            commands.entity(ent).insert(LdtkMapHasLoaded);
+    // Update the rendered sublayers
+    for (key, tilemap_layer) in
+        keyed_layer_tilemaps(ctx, layer, level_index, layer_index, int_grid.as_ref())
+    {
+        let current = spawned.remove(&key).and_then(|entity| {
+            let current = items.tilemap_layers.get_mut(entity).ok()?;
+            Some((entity, current))
+        });
+        match current {
+            Some((entity, mut current)) => {
+                // The animated tiles may have changed along with the tiles
+                match layer_animated_tiles(ctx, layer, &tilemap_layer) {
+                    Some(animated_tiles) => commands.entity(entity).insert(animated_tiles),
+                    None => commands.entity(entity).remove::<LdtkAnimatedTiles>(),
+                };
+
+                update_tilemap_layer(&mut current, tilemap_layer);
+            }
+            None => spawn_tilemap_layer(commands, ctx, level_ent, key, layer, tilemap_layer),
         }
     }
 }
 
+/// Update a spawned tilemap layer, only marking it as changed if something is different so that
+/// unchanged layers aren't sent to the GPU again
+fn update_tilemap_layer(current: &mut Mut<LdtkTilemapLayer>, new: LdtkTilemapLayer) {
+    if current.tiles != new.tiles {
+        current.tiles = new.tiles;
+    }
+    if current.map_info != new.map_info {
+        current.map_info = new.map_info;
+    }
+    if current.tileset_info != new.tileset_info {
+        current.tileset_info = new.tileset_info;
+    }
+    if current.texture != new.texture {
+        current.texture = new.texture;
+    }
+    if current.scale != new.scale {
+        current.scale = new.scale;
+    }
+    if current.display_info != new.display_info {
+        current.display_info = new.display_info;
+    }
+}
+
 type MapEvent = AssetEvent<LdtkMap>;
+type LevelEvent = AssetEvent<LdtkLevel>;
 
 /// This system watches for changes to map assets and makes sure that the map is reloaded upon
 /// changes.
+#[allow(clippy::too_many_arguments)]
 fn hot_reload_maps(
     mut commands: Commands,
     mut event_reader: EventReader<MapEvent>,
-    layers: Query<(Entity, &LayerMapHandle)>,
-    maps: Query<(Entity, &Handle<LdtkMap>), With<LdtkMapConfig>>,
+    mut level_event_reader: EventReader<LevelEvent>,
+    mut map_assets: ResMut<Assets<LdtkMap>>,
+    level_assets: Res<Assets<LdtkLevel>>,
+    maps: Query<(
+        Entity,
+        &Handle<LdtkMap>,
+        &LdtkMapConfig,
+        Option<&LdtkMapHasLoaded>,
+    )>,
+    levels: SpawnedLevels,
+    items: Query<&'static SpawnedFrom>,
+    tilemap_layers: Query<&'static mut LdtkTilemapLayer>,
+    int_grids: Query<&'static mut LdtkIntGrid>,
+    ldtk_entities: Query<&'static mut LdtkEntity>,
+    transforms: Query<&'static mut Transform, With<SpawnedFrom>>,
+    entity_registry: Res<LdtkEntityRegistry>,
+    collider_registry: Res<LdtkColliderRegistry>,
+    mut map_events: EventWriter<LdtkMapEvent>,
 ) {
-    // Here we create a simple macro that just pastes our event handler code
-    macro_rules! handle_map_event {
-        ($event:ident) => {
-            match $event {
-                // When the map asset has been modified
-                AssetEvent::Modified { handle } => {
-                    // Loop through all the layers in the world, find the ones that are for this map and remove them
-                    for (layer_ent, LayerMapHandle(map_handle)) in layers.iter() {
-                        if map_handle == handle {
-                            commands.entity(layer_ent).despawn();
-                        }
+    let mut items = SpawnedMapItems {
+        levels,
+        items,
+        tilemap_layers,
+        int_grids,
+        ldtk_entities,
+        transforms,
+    };
+
+    for event in event_reader.iter() {
+        // When the map asset has been modified
+        let handle = match event {
+            AssetEvent::Modified { handle } => handle,
+            _ => continue,
+        };
+
+        for (map_ent, map_handle, config, has_loaded) in maps.iter() {
+            if map_handle != handle {
+                continue;
+            }
+
+            // Try to update the spawned map in place
+            let reloaded = has_loaded.is_some()
+                && map_assets
+                    .get(handle)
+                    .and_then(|map| {
+                        MapSpawnContext::new(map, config, &entity_registry, &collider_registry).ok()
+                    })
+                    .map(|ctx| reload_map(&mut commands, &ctx, map_ent, &mut items))
+                    .unwrap_or(false);
+
+            // Otherwise despawn the levels of the map, and remove the `LdtkMapHasLoaded` component
+            // from the map so that it will be re-spawned by the `process_ldtk_maps` system.
+            if !reloaded {
+                for (level_ent, _, _, parent, _) in items.levels.iter() {
+                    if parent.0 == map_ent {
+                        commands.entity(level_ent).despawn_recursive();
                     }
+                }
+                commands.entity(map_ent).remove::<LdtkMapHasLoaded>();
 
-                    // Then remove the `LdtkMapHasLoaded` component from the map so that it will be
-                    // reloaded by the `process_ldtk_maps` system.
-                    for (map_ent, map_handle) in maps.iter() {
-                        if map_handle == handle {
-                            commands.entity(map_ent).remove::<LdtkMapHasLoaded>();
-                        }
+                map_events.send(LdtkMapEvent::Despawned {
+                    map: map_ent,
+                    levels: config.levels.clone(),
+                });
+            } else {
+                map_events.send(LdtkMapEvent::Reloaded {
+                    map: map_ent,
+                    levels: config.levels.clone(),
+                });
+            }
+        }
+    }
+
+    // When an external level file changes, copy its layers into the map that references it. This
+    // will trigger a `Modified` event for the map, which will cause it to be reloaded above.
+    for event in level_event_reader.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            // Get the updated level
+            let external_level = if let Some(level) = level_assets.get(handle) {
+                level
+            } else {
+                continue;
+            };
+
+            // Find all of the maps that use this level file
+            let changed_maps = map_assets
+                .iter()
+                .flat_map(|(map_id, map)| {
+                    map.external_levels
+                        .iter()
+                        .filter(|(_, level_handle)| *level_handle == handle)
+                        .map(move |(level_uid, _)| (map_id, *level_uid))
+                })
+                .collect::<Vec<_>>();
+
+            for (map_id, level_uid) in changed_maps {
+                if let Some(map) = map_assets.get_mut(map_id) {
+                    // Update the level's layers
+                    if let Some(level) = map.project.levels.iter_mut().find(|x| x.uid == level_uid)
+                    {
+                        level.layer_instances = external_level.level.layer_instances.clone();
                     }
+                    map.entity_iids
+                        .insert(level_uid, external_level.entity_iids.clone());
                 }
-                _ => (),
             }
         }
     }
+}
 
-    for event in event_reader.iter() {
-        handle_map_event!(event);
+/// Get the world position of the cursor in the primary window, as seen by the 2D camera
+fn cursor_world_position(
+    windows: &Windows,
+    cameras: &Query<(&Camera, &GlobalTransform, &OrthographicProjection)>,
+) -> Option<Vec3> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+
+    let (_, transform, projection) = cameras.iter().find(|(camera, ..)| {
+        camera.window == window.id() && camera.name.as_deref() == Some(CAMERA_2D)
+    })?;
+
+    // Map the cursor from window pixels to the edges of the camera's projection
+    let t = cursor / window_size;
+    let local = Vec2::new(
+        projection.left + (projection.right - projection.left) * t.x,
+        projection.bottom + (projection.top - projection.bottom) * t.y,
+    ) * projection.scale;
+
+    Some(transform.mul_vec3(local.extend(0.0)))
+}
+
+/// This system picks the tiles under the cursor and sends [`LdtkTileHovered`] and
+/// [`LdtkTileClicked`] events
+///
+/// Does nothing in apps without windows or mouse input, like headless servers and tests.
+fn pick_cursor_tiles(
+    mut hovered_tiles: Local<Vec<LdtkTilePick>>,
+    mut hovered_events: EventWriter<LdtkTileHovered>,
+    mut clicked_events: EventWriter<LdtkTileClicked>,
+    windows: Option<Res<Windows>>,
+    mouse_input: Option<Res<Input<MouseButton>>>,
+    cameras: Query<(&Camera, &GlobalTransform, &OrthographicProjection)>,
+    picker: LdtkTilePicker,
+) {
+    let (windows, mouse_input) = match (windows, mouse_input) {
+        (Some(windows), Some(mouse_input)) => (windows, mouse_input),
+        _ => return,
+    };
+
+    let (position, tiles) = match cursor_world_position(&windows, &cameras) {
+        Some(position) => (position, picker.pick(position)),
+        None => (Vec3::ZERO, Vec::new()),
+    };
+
+    if tiles != *hovered_tiles {
+        hovered_events.send(LdtkTileHovered {
+            position,
+            tiles: tiles.clone(),
+        });
+    }
+
+    if !tiles.is_empty() {
+        for button in mouse_input.get_just_pressed() {
+            clicked_events.send(LdtkTileClicked {
+                button: *button,
+                position,
+                tiles: tiles.clone(),
+            });
+        }
+    }
+
+    *hovered_tiles = tiles;
+}
+
+/// This system advances the tile animations of every map and updates the animated tiles of the
+/// rendered layers
+fn animate_tiles(
+    mut elapsed: Local<HashMap<Entity, f32>>,
+    time: Res<Time>,
+    maps: Query<(Entity, &Handle<LdtkMap>, Option<&LdtkTileAnimationConfig>)>,
+    levels: Query<&Parent, With<LdtkMapLevel>>,
+    mut layers: Query<(&LdtkAnimatedTiles, &Parent, &mut LdtkTilemapLayer)>,
+    map_assets: Res<Assets<LdtkMap>>,
+) {
+    // Advance the animation time of every map, forgetting about maps that have been despawned
+    let mut map_times = HashMap::default();
+    for (map_ent, _, config) in maps.iter() {
+        let mut map_time = elapsed.get(&map_ent).copied().unwrap_or_default();
+        match config {
+            Some(config) if config.paused => (),
+            Some(config) => map_time += time.delta_seconds() * config.speed,
+            None => map_time += time.delta_seconds(),
+        }
+        map_times.insert(map_ent, map_time);
+    }
+    *elapsed = map_times;
+
+    for (animated_tiles, parent, mut tilemap_layer) in layers.iter_mut() {
+        // Get the map that the layer belongs to
+        let map_ent = match levels.get(parent.0) {
+            Ok(level_parent) => level_parent.0,
+            Err(_) => continue,
+        };
+        let (map_time, animations) = match (
+            elapsed.get(&map_ent),
+            maps.get(map_ent)
+                .ok()
+                .and_then(|(_, handle, _)| map_assets.get(handle))
+                .and_then(|map| map.tile_animations.get(&animated_tiles.tileset_uid)),
+        ) {
+            (Some(map_time), Some(animations)) => (*map_time, animations),
+            _ => continue,
+        };
+
+        for &(i, tile_id) in &animated_tiles.tiles {
+            let animation = match animations.get(&tile_id) {
+                Some(animation) => animation,
+                None => continue,
+            };
+            let current = match tilemap_layer.tiles.get(i) {
+                Some(tile) => tile.tile_index,
+                None => continue,
+            };
+
+            // Leave tiles that have been changed to something else alone, and only mark the layer
+            // as changed if the frame actually changes
+            let is_animated = current == tile_id || animation.has_frame(current);
+            let frame = match animation.frame_at(map_time) {
+                Some(frame) => frame,
+                None => continue,
+            };
+            if current != frame && is_animated {
+                tilemap_layer.tiles[i].tile_index = frame;
+            }
+        }
+    }
+}
+
+/// This system moves the rendered layers that have parallax settings relative to the active 2D
+/// camera
+#[allow(clippy::type_complexity)]
+fn apply_parallax(
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    maps: Query<&Handle<LdtkMap>>,
+    levels: Query<
+        (&LdtkMapLevel, &Transform, &GlobalTransform, &Parent),
+        Without<LdtkTilemapLayer>,
+    >,
+    mut layers: Query<
+        (&SpawnedFrom, &Parent, Option<&LdtkParallax>, &mut Transform),
+        With<LdtkTilemapLayer>,
+    >,
+    map_assets: Res<Assets<LdtkMap>>,
+) {
+    let camera_position = match cameras
+        .iter()
+        .find(|(camera, _)| camera.name.as_deref() == Some(CAMERA_2D))
+    {
+        Some((_, transform)) => transform.translation,
+        None => return,
+    };
+
+    for (spawned_from, parent, parallax_override, mut transform) in layers.iter_mut() {
+        let layer_def_uid = match spawned_from {
+            SpawnedFrom::Tiles { layer_def_uid, .. }
+            | SpawnedFrom::YSortedRow { layer_def_uid, .. }
+            | SpawnedFrom::TileChunk { layer_def_uid, .. } => *layer_def_uid,
+            _ => continue,
+        };
+        let (level, level_transform, level_global_transform, level_parent) =
+            match levels.get(parent.0) {
+                Ok(level) => level,
+                Err(_) => continue,
+            };
+
+        // Use the layer's override if it has one, otherwise use the layer definition's settings
+        let parallax = match parallax_override {
+            Some(parallax) => *parallax,
+            None => match maps
+                .get(level_parent.0)
+                .ok()
+                .and_then(|handle| map_assets.get(handle))
+                .and_then(|map| map.layer_parallax.get(&layer_def_uid))
+            {
+                Some(parallax) => *parallax,
+                // Leave the transforms of layers without parallax alone
+                None => continue,
+            },
+        };
+
+        // Get the level center and the camera position relative to the level entity. The level
+        // bounds are relative to the map entity, which the level is offset from by its transform.
+        let level_center =
+            (level.bounds.min + level.bounds.max) / 2.0 - level_transform.translation.truncate();
+        let camera = level_global_transform
+            .compute_matrix()
+            .inverse()
+            .transform_point3(camera_position)
+            .truncate();
+
+        // Only mark the transform as changed if the layer actually moved
+        let new_transform = parallax.layer_transform(level_center, camera);
+        if *transform != new_transform {
+            *transform = new_transform;
+        }
+    }
+}
+
+/// This system places the entities with an `LdtkYSorted` component on the Z axis according to
+/// their Y coordinate relative to their map
+fn y_sort_entities(
+    maps: Query<(&LdtkMapConfig, &GlobalTransform)>,
+    parents: Query<&Parent>,
+    global_transforms: Query<&GlobalTransform>,
+    mut sorted: Query<(Entity, &LdtkYSorted, Option<&Parent>, &mut Transform)>,
+) {
+    for (entity, y_sorted, parent, mut transform) in sorted.iter_mut() {
+        // Find the map entity, walking up the parents if it wasn't given
+        let map_ent = match y_sorted.map {
+            Some(map_ent) => Some(map_ent),
+            None => {
+                let mut current = entity;
+                loop {
+                    if maps.get(current).is_ok() {
+                        break Some(current);
+                    }
+                    match parents.get(current) {
+                        Ok(parent) => current = parent.0,
+                        Err(_) => break None,
+                    }
+                }
+            }
+        };
+        let (y_sort, map_matrix) = match map_ent.and_then(|x| maps.get(x).ok()) {
+            Some((config, map_transform)) => match &config.y_sort {
+                Some(y_sort) => (y_sort, map_transform.compute_matrix()),
+                None => continue,
+            },
+            None => continue,
+        };
+
+        // Use the entity's current transform instead of its global transform, which isn't updated
+        // until the end of the frame
+        let parent_matrix = parent
+            .and_then(|parent| global_transforms.get(parent.0).ok())
+            .map(|x| x.compute_matrix())
+            .unwrap_or(Mat4::IDENTITY);
+        let world = parent_matrix.transform_point3(transform.translation);
+
+        // Get the Z coordinate for the entity's Y coordinate relative to the map, and bring it back
+        // into the entity's parent's space
+        let local = map_matrix.inverse().transform_point3(world);
+        let sorted_world = map_matrix.transform_point3(local.truncate().extend(y_sort.z(local.y)));
+        let z = parent_matrix.inverse().transform_point3(sorted_world).z;
+
+        // Only mark the transform as changed if the entity actually moved
+        if transform.translation.z != z {
+            transform.translation.z = z;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::{Events, ManualEventReader},
+        asset::AssetPlugin,
+        core::CorePlugin,
+    };
+
+    use super::*;
+
+    struct Player;
+
+    /// The uid of the only level in the test map
+    const LEVEL_UID: i32 = 70;
+    /// The uid of the entities layer in the test map
+    const ENTITIES_LAYER_UID: i32 = 113;
+
+    fn test_app() -> App {
+        let mut builder = App::build();
+        builder.add_plugin(CorePlugin).add_plugin(AssetPlugin);
+        crate::asset::add_assets(&mut builder);
+        crate::events::add_events(&mut builder);
+        builder
+            .init_resource::<LdtkEntityRegistry>()
+            .init_resource::<LdtkColliderRegistry>()
+            .init_resource::<ClearColor>()
+            .register_ldtk_entity("Player_Spawn", |_| (Player,))
+            .add_system(process_ldtk_maps.system())
+            .add_system(hot_reload_maps.system())
+            .add_system(stream_levels.system())
+            .add_system(apply_parallax.system())
+            .add_system(track_levels.system())
+            .add_system(pick_cursor_tiles.system());
+        builder.app
+    }
+
+    /// Load the example map, giving its player spawn the iid `player-a`
+    fn test_map() -> LdtkMap {
+        let mut tile_sets = HashMap::default();
+        tile_sets.insert("Cavernas_by_Adam_Saltsman".into(), Handle::default());
+
+        let mut layer_iids = HashMap::default();
+        layer_iids.insert(ENTITIES_LAYER_UID, vec!["player-a".to_string()]);
+        let mut entity_iids = HashMap::default();
+        entity_iids.insert(LEVEL_UID, layer_iids);
+        let mut level_iids = HashMap::default();
+        level_iids.insert(LEVEL_UID, "level-a".to_string());
+
+        LdtkMap {
+            project: serde_json::from_str(include_str!("../assets/map1.ldtk")).unwrap(),
+            tile_sets,
+            external_levels: Default::default(),
+            int_grid_palettes: Default::default(),
+            tileset_meta: Default::default(),
+            tile_animations: Default::default(),
+            layer_parallax: Default::default(),
+            entity_iids,
+            level_iids,
+        }
+    }
+
+    /// Spawn a map and wait for it to load
+    fn spawn_test_map(app: &mut App, map: LdtkMap) -> Handle<LdtkMap> {
+        let handle = app
+            .world
+            .get_resource_mut::<Assets<LdtkMap>>()
+            .unwrap()
+            .add(map);
+        app.world.spawn().insert_bundle(LdtkMapBundle {
+            map: handle.clone(),
+            ..Default::default()
+        });
+        app.update();
+
+        handle
+    }
+
+    /// Change the entity instances and iids of the entities layer in the map asset, and let the
+    /// map hot reload
+    fn edit_entities(
+        app: &mut App,
+        handle: &Handle<LdtkMap>,
+        edit: impl FnOnce(&mut Vec<ldtk::EntityInstance>, &mut Vec<String>),
+    ) {
+        let mut maps = app.world.get_resource_mut::<Assets<LdtkMap>>().unwrap();
+        let map = maps.get_mut(handle).unwrap();
+        let layer = map.project.levels[0]
+            .layer_instances
+            .as_mut()
+            .unwrap()
+            .iter_mut()
+            .find(|x| x.layer_def_uid == ENTITIES_LAYER_UID)
+            .unwrap();
+        let iids = map
+            .entity_iids
+            .get_mut(&LEVEL_UID)
+            .unwrap()
+            .get_mut(&ENTITIES_LAYER_UID)
+            .unwrap();
+        edit(&mut layer.entity_instances, iids);
+
+        // Let the modified event get sent and handled
+        app.update();
+        app.update();
+    }
+
+    /// Get the spawned players, along with their positions in the map
+    fn players(app: &mut App) -> Vec<(Entity, Vec<i32>, Vec3)> {
+        let mut query = app
+            .world
+            .query_filtered::<(Entity, &LdtkEntity, &Transform), With<Player>>();
+        let mut players = query
+            .iter(&app.world)
+            .map(|(entity, ldtk_entity, transform)| {
+                (
+                    entity,
+                    ldtk_entity.instance.px.clone(),
+                    transform.translation,
+                )
+            })
+            .collect::<Vec<_>>();
+        players.sort_by_key(|(_, px, _)| px.clone());
+
+        players
+    }
+
+    #[test]
+    fn entity_instances_are_keyed_by_iid_when_available() {
+        let map = test_map();
+        let layer = map.project.levels[0]
+            .layer_instances
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|x| x.layer_def_uid == ENTITIES_LAYER_UID)
+            .unwrap();
+        let def_uid = layer.entity_instances[0].def_uid;
+
+        let keys = layer_entity_instances(layer, Some(&["player-a".to_string()]))
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![SpawnedFrom::EntityIid {
+                layer_def_uid: ENTITIES_LAYER_UID,
+                iid: "player-a".into(),
+            }]
+        );
+
+        // Layers without iids fall back to the index of the instance
+        let keys = layer_entity_instances(layer, None)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![SpawnedFrom::Entity {
+                layer_def_uid: ENTITIES_LAYER_UID,
+                def_uid,
+                index: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn layers_without_tiles_are_spawned() {
+        let mut app = test_app();
+        let mut map = test_map();
+        for layer in map.project.levels[0].layer_instances.as_mut().unwrap() {
+            layer.auto_layer_tiles.clear();
+            layer.grid_tiles.clear();
+        }
+        spawn_test_map(&mut app, map);
+
+        let mut layers = app.world.query::<(&LdtkTileLayer, &LdtkTilemapLayer)>();
+        let mut identifiers = layers
+            .iter(&app.world)
+            .map(|(tile_layer, tilemap_layer)| {
+                assert!(tilemap_layer.tiles.iter().all(|x| x.is_empty()));
+                tile_layer.identifier.as_str()
+            })
+            .collect::<Vec<_>>();
+        identifiers.sort_unstable();
+        assert_eq!(identifiers, vec!["Background", "IntGrid_layer"]);
+    }
+
+    #[test]
+    fn levels_are_selected_by_iid() {
+        let map = test_map();
+        let select = |iid: &str| LevelSelection::Iids(vec![iid.into()]).select(&map);
+        assert_eq!(select("level-a").unwrap(), vec![0]);
+        assert!(select("level-b").is_err());
+
+        let mut app = test_app();
+        spawn_test_map(&mut app, test_map());
+
+        let mut levels = app.world.query::<&LdtkMapLevel>();
+        let iids = levels
+            .iter(&app.world)
+            .map(|x| x.iid.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(iids, vec![Some("level-a")]);
+    }
+
+    #[test]
+    fn level_events_name_the_level_iid() {
+        let mut app = test_app();
+        spawn_test_map(&mut app, test_map());
+
+        let mut levels = app.world.query::<&LdtkMapLevel>();
+        let bounds = levels.iter(&app.world).next().unwrap().bounds;
+        let center = (bounds.min + bounds.max) / 2.0;
+        let tracker = app
+            .world
+            .spawn()
+            .insert(LdtkLevelTracker)
+            .insert(GlobalTransform::from_xyz(center.x, center.y, 0.))
+            .id();
+
+        let mut entered_reader = app
+            .world
+            .get_resource::<Events<LevelEntered>>()
+            .unwrap()
+            .get_reader();
+        let mut exited_reader = app
+            .world
+            .get_resource::<Events<LevelExited>>()
+            .unwrap()
+            .get_reader();
+
+        app.update();
+        let events = app.world.get_resource::<Events<LevelEntered>>().unwrap();
+        let entered = entered_reader.iter(events).collect::<Vec<_>>();
+        assert_eq!(entered.len(), 1);
+        assert_eq!(entered[0].uid, LEVEL_UID);
+        assert_eq!(entered[0].iid.as_deref(), Some("level-a"));
+
+        app.world.despawn(tracker);
+        app.update();
+        let events = app.world.get_resource::<Events<LevelExited>>().unwrap();
+        let exited = exited_reader.iter(events).collect::<Vec<_>>();
+        assert_eq!(exited.len(), 1);
+        assert_eq!(exited[0].iid.as_deref(), Some("level-a"));
+    }
+
+    #[test]
+    fn centered_regions_line_up_with_the_whole_layer() {
+        // An odd number of pixels wide and tall, so the center of the layer is between two pixels
+        let layer = LdtkTilemapLayer {
+            scale: 2.0,
+            map_info: LdtkTilemapMapInfo {
+                width: 5,
+                height: 3,
+                grid_size: 3,
+                center_map: 1,
+                offset_x: 1,
+                offset_y: 2,
+                layer_width: 5,
+                layer_height: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let region = LdtkTilemapLayer {
+            scale: 2.0,
+            map_info: region_map_info(&layer.map_info, 2, 1, 2, 1),
+            ..Default::default()
+        };
+
+        let transform = GlobalTransform::default();
+        let layer_geometry = LdtkLayerGeometry::from_tilemap_layer(&transform, &layer);
+        let region_geometry = LdtkLayerGeometry::from_tilemap_layer(&transform, &region);
+        assert_eq!(
+            region_geometry.ldtk_px_to_world(Vec2::ZERO),
+            layer_geometry.ldtk_px_to_world(Vec2::new(6.0, 3.0))
+        );
+        assert_eq!(
+            region_geometry.world_to_cell(layer_geometry.cell_to_world(3, 1)),
+            Some((1, 0))
+        );
+    }
+
+    /// Get the map events that have been sent since the last time the reader was used
+    fn map_events(app: &App, reader: &mut ManualEventReader<LdtkMapEvent>) -> Vec<LdtkMapEvent> {
+        let events = app.world.get_resource::<Events<LdtkMapEvent>>().unwrap();
+        reader.iter(events).cloned().collect()
+    }
+
+    #[test]
+    fn hot_reload_only_sends_reloaded_when_updated_in_place() {
+        let mut app = test_app();
+        let handle = spawn_test_map(&mut app, test_map());
+        let mut reader = app
+            .world
+            .get_resource::<Events<LdtkMapEvent>>()
+            .unwrap()
+            .get_reader();
+        map_events(&app, &mut reader);
+
+        // Moving an entity updates the map in place
+        edit_entities(&mut app, &handle, |instances, _| {
+            instances[0].px = vec![100, 50];
+        });
+        let events = map_events(&app, &mut reader);
+        assert!(events
+            .iter()
+            .any(|x| matches!(x, LdtkMapEvent::Reloaded { .. })));
+        assert!(!events
+            .iter()
+            .any(|x| matches!(x, LdtkMapEvent::Despawned { .. })));
+
+        // Removing a layer makes the map re-spawn
+        let mut maps = app.world.get_resource_mut::<Assets<LdtkMap>>().unwrap();
+        let map = maps.get_mut(&handle).unwrap();
+        map.project.levels[0]
+            .layer_instances
+            .as_mut()
+            .unwrap()
+            .retain(|x| x.layer_def_uid != ENTITIES_LAYER_UID);
+        app.update();
+        app.update();
+        app.update();
+
+        let events = map_events(&app, &mut reader);
+        assert!(!events
+            .iter()
+            .any(|x| matches!(x, LdtkMapEvent::Reloaded { .. })));
+        assert!(events
+            .iter()
+            .any(|x| matches!(x, LdtkMapEvent::Despawned { .. })));
+        assert!(events
+            .iter()
+            .any(|x| matches!(x, LdtkMapEvent::Spawned { .. })));
+    }
+
+    #[test]
+    fn streamed_level_errors_are_reported_once() {
+        let mut app = test_app();
+        let mut map = test_map();
+        map.project.levels[0].layer_instances = None;
+        let handle = app
+            .world
+            .get_resource_mut::<Assets<LdtkMap>>()
+            .unwrap()
+            .add(map);
+        let map_ent = app
+            .world
+            .spawn()
+            .insert_bundle(LdtkMapBundle {
+                map: handle,
+                config: LdtkMapConfig {
+                    levels: LevelSelection::Streamed,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .id();
+        app.world
+            .spawn()
+            .insert(LdtkLevelStreamer::new(map_ent, 10000.))
+            .insert(GlobalTransform::default());
+
+        let mut reader = app
+            .world
+            .get_resource::<Events<LdtkMapError>>()
+            .unwrap()
+            .get_reader();
+        let mut errors = 0;
+        for _ in 0..5 {
+            app.update();
+            let events = app.world.get_resource::<Events<LdtkMapError>>().unwrap();
+            errors += reader.iter(events).count();
+        }
+
+        assert_eq!(errors, 1);
+        let mut levels = app.world.query::<&LdtkMapLevel>();
+        assert_eq!(levels.iter(&app.world).count(), 0);
+    }
+
+    #[test]
+    fn parallax_leaves_layers_without_parallax_alone() {
+        let mut app = test_app();
+        spawn_test_map(&mut app, test_map());
+        app.world
+            .spawn()
+            .insert(Camera {
+                name: Some(CAMERA_2D.into()),
+                ..Default::default()
+            })
+            .insert(GlobalTransform::from_xyz(100., -50., 0.));
+
+        // Move the layers at runtime
+        let mut layers = app
+            .world
+            .query_filtered::<&mut Transform, With<LdtkTilemapLayer>>();
+        for mut transform in layers.iter_mut(&mut app.world) {
+            transform.translation.x = 5.;
+        }
+
+        app.update();
+
+        let mut layers = app
+            .world
+            .query_filtered::<&Transform, With<LdtkTilemapLayer>>();
+        for transform in layers.iter(&app.world) {
+            assert_eq!(transform.translation.x, 5.);
+        }
+    }
+
+    #[test]
+    fn hot_reload_keeps_unchanged_entities() {
+        let mut app = test_app();
+        let handle = spawn_test_map(&mut app, test_map());
+        let (player, ..) = players(&mut app)[0];
+
+        // Move the player at runtime
+        app.world
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation
+            .x = 1000.;
+
+        edit_entities(&mut app, &handle, |_, _| ());
+
+        let players = players(&mut app);
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].0, player);
+        assert_eq!(players[0].2.x, 1000.);
+    }
+
+    #[test]
+    fn hot_reload_moves_moved_entities() {
+        let mut app = test_app();
+        let handle = spawn_test_map(&mut app, test_map());
+        let (player, _, translation) = players(&mut app)[0];
+
+        edit_entities(&mut app, &handle, |instances, _| {
+            instances[0].px = vec![100, 50];
+        });
+
+        let players = players(&mut app);
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].0, player);
+        assert_eq!(players[0].1, vec![100, 50]);
+        assert_ne!(players[0].2, translation);
+    }
+
+    #[test]
+    fn hot_reload_spawns_added_entities() {
+        let mut app = test_app();
+        let handle = spawn_test_map(&mut app, test_map());
+        let (player, ..) = players(&mut app)[0];
+
+        edit_entities(&mut app, &handle, |instances, iids| {
+            let mut instance = instances[0].clone();
+            instance.px = vec![10, 10];
+            instances.insert(0, instance);
+            iids.insert(0, "player-b".into());
+        });
+
+        let players = players(&mut app);
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0].1, vec![10, 10]);
+        assert_ne!(players[0].0, player);
+        assert_eq!(players[1].0, player);
+    }
+
+    #[test]
+    fn hot_reload_despawns_removed_entities() {
+        let mut app = test_app();
+        let handle = spawn_test_map(&mut app, test_map());
+
+        edit_entities(&mut app, &handle, |instances, iids| {
+            let mut instance = instances[0].clone();
+            instance.px = vec![10, 10];
+            instances.push(instance);
+            iids.push("player-b".into());
+        });
+        let (player_b, ..) = players(&mut app)[0];
+
+        // Remove the first instance, which moves the second instance to its index
+        edit_entities(&mut app, &handle, |instances, iids| {
+            instances.remove(0);
+            iids.remove(0);
+        });
+
+        let players = players(&mut app);
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].0, player_b);
+        assert_eq!(players[0].1, vec![10, 10]);
+    }
+
+    #[test]
+    fn hot_reload_keeps_runtime_edits_to_unchanged_layers() {
+        let mut app = test_app();
+        let handle = spawn_test_map(&mut app, test_map());
+
+        // Edit the background layer at runtime
+        let mut layers = app.world.query::<(&LdtkTileLayer, &mut LdtkTilemapLayer)>();
+        let mut edited = 0;
+        for (tile_layer, mut tilemap_layer) in layers.iter_mut(&mut app.world) {
+            if tile_layer.identifier == "Background" {
+                tilemap_layer.tiles[0] = LdtkTilemapTileInfo {
+                    tile_index: 1,
+                    flip_bits: 0,
+                };
+                tilemap_layer.display_info.opacity = 0.5;
+                edited += 1;
+            }
+        }
+        assert!(edited > 0);
+
+        // Move the player spawn in the map asset
+        edit_entities(&mut app, &handle, |instances, _| {
+            instances[0].px = vec![100, 50];
+        });
+        assert_eq!(players(&mut app)[0].1, vec![100, 50]);
+
+        // The edits to the background layer survived
+        let mut layers = app.world.query::<(&LdtkTileLayer, &LdtkTilemapLayer)>();
+        for (tile_layer, tilemap_layer) in layers.iter(&app.world) {
+            if tile_layer.identifier == "Background" {
+                assert_eq!(tilemap_layer.tiles[0].tile_index, 1);
+                assert_eq!(tilemap_layer.display_info.opacity, 0.5);
+            }
+        }
     }
 }
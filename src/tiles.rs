@@ -0,0 +1,516 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{LdtkTilemapDisplayInfo, LdtkTilemapLayer, LdtkTilemapTileInfo};
+
+/// A component added to every rendered layer entity, identifying the LDtk layer that it renders
+///
+/// LDtk layers can have more than one tile in the same cell, so a layer may be rendered by several
+/// sublayer entities, each with their own [`LdtkTilemapLayer`]. When the map has a chunk size, each
+/// sublayer is also split into chunks that are rendered by separate entities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdtkTileLayer {
+    /// The identifier of the LDtk layer
+    pub identifier: String,
+    /// The index of the sublayer, with 0 being the lowest sublayer
+    pub sublayer_index: usize,
+    /// The row of the layer that this entity renders, for the rows of Y-sorted tiles that are
+    /// split out of the layer. This is `None` for entities that render the whole layer.
+    pub row: Option<u32>,
+    /// The coordinates of the top-left cell of the chunk of the layer that this entity renders, or
+    /// `None` if the layer isn't split into chunks
+    pub chunk_origin: Option<(u32, u32)>,
+}
+
+impl LdtkTileLayer {
+    /// Get the coordinates in the LDtk layer of the top-left cell that this entity renders
+    pub fn cell_origin(&self) -> (u32, u32) {
+        let (x, y) = self.chunk_origin.unwrap_or((0, 0));
+        (x, y + self.row.unwrap_or(0))
+    }
+}
+
+impl LdtkTilemapTileInfo {
+    /// A tile that doesn't render anything
+    pub const EMPTY: LdtkTilemapTileInfo = LdtkTilemapTileInfo {
+        tile_index: u32::MAX,
+        flip_bits: 0,
+    };
+
+    /// Create the info for the tile with the given index in the tileset
+    pub fn new(tile_index: u32, flip: ldtk::TileFlip) -> Self {
+        LdtkTilemapTileInfo {
+            tile_index,
+            flip_bits: if flip.x { 1 } else { 0 } | if flip.y { 2 } else { 0 },
+        }
+    }
+
+    /// Get whether or not this is an empty tile
+    pub fn is_empty(&self) -> bool {
+        self.tile_index == u32::MAX
+    }
+
+    /// Get how the tile is flipped
+    pub fn flip(&self) -> ldtk::TileFlip {
+        ldtk::TileFlip {
+            x: self.flip_bits & 1 != 0,
+            y: self.flip_bits & 2 != 0,
+        }
+    }
+}
+
+impl LdtkTilemapLayer {
+    /// Get the index in the `tiles` buffer of the cell at the given coordinates, with (0, 0) being
+    /// the top-left cell
+    ///
+    /// The tiles are stored from top to bottom, but from right to left, to match the order that
+    /// the shader draws them in. Returns `None` if the coordinates are outside of the layer.
+    pub fn tile_buffer_index(&self, x: u32, y: u32) -> Option<usize> {
+        let width = self.map_info.width;
+        if x >= width || y >= self.map_info.height {
+            return None;
+        }
+
+        Some((y * width + (width - 1 - x)) as usize)
+    }
+
+    /// Get the tile in the cell at the given coordinates, if there is one
+    pub fn get_tile(&self, x: u32, y: u32) -> Option<LdtkTilemapTileInfo> {
+        self.tile_buffer_index(x, y)
+            .and_then(|i| self.tiles.get(i))
+            .copied()
+            .filter(|tile| !tile.is_empty())
+    }
+}
+
+/// A system parameter used to edit the tiles and the display of the spawned layers at runtime
+///
+/// # Example
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_ldtk::*;
+///
+/// // Break the wall tile that the player is standing next to
+/// fn break_wall(mut tile_layers: LdtkTileLayers, levels: Query<Entity, With<LdtkMapLevel>>) {
+///     for level in levels.iter() {
+///         if let Some(mut walls) = tile_layers.layer_mut(level, "Walls") {
+///             walls.clear_tile(4, 2);
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct LdtkTileLayers<'a> {
+    layers: Query<
+        'a,
+        (
+            &'static LdtkTileLayer,
+            &'static Parent,
+            &'static mut LdtkTilemapLayer,
+        ),
+    >,
+}
+
+impl<'a> LdtkTileLayers<'a> {
+    /// Get the layer with the given identifier in a spawned level, where `level` is the entity with
+    /// the [`LdtkMapLevel`] component
+    ///
+    /// Returns `None` if the level doesn't have a rendered layer with that identifier. Layers with
+    /// a tileset are always rendered, even when they don't have any tiles, but IntGrid layers
+    /// without a tileset are only rendered when [`LdtkMapConfig::render_int_grid`] is set. The rows
+    /// of Y-sorted tiles that are split out of the layer are included, so their tiles can be edited
+    /// and they are displayed the same way as the rest of the layer.
+    ///
+    /// [`LdtkMapLevel`]: crate::LdtkMapLevel
+    /// [`LdtkMapConfig::render_int_grid`]: crate::LdtkMapConfig::render_int_grid
+    pub fn layer_mut(&mut self, level: Entity, identifier: &str) -> Option<TileLayerMut<'_>> {
+        TileLayerMut::new(
+            self.layers
+                .iter_mut()
+                .filter(|(layer, parent, _)| parent.0 == level && layer.identifier == identifier)
+                .map(|(layer, _, tilemap_layer)| (layer, tilemap_layer)),
+        )
+    }
+}
+
+/// Mutable access to the tiles and the display of a spawned layer, across all of its sublayers
+///
+/// Changing the tiles or the display marks the layer's render resources as changed, so that the
+/// change will show up in the next frame. When the layer is split into chunks, only the chunks
+/// with tiles that change are marked.
+pub struct TileLayerMut<'a> {
+    /// The number of cells wide and tall the layer is
+    size: (u32, u32),
+    /// The sublayers, or the chunks of the sublayers, ordered from lowest to highest
+    sublayers: Vec<TileLayerPart<'a>>,
+}
+
+/// A rendered sublayer, or a chunk of one, in a [`TileLayerMut`]
+struct TileLayerPart<'a> {
+    /// The coordinates in the layer of the top-left cell of the part
+    origin: (u32, u32),
+    tilemap_layer: Mut<'a, LdtkTilemapLayer>,
+}
+
+impl<'a> TileLayerPart<'a> {
+    /// Get the index in the `tiles` buffer of the cell at the given layer coordinates, if the cell
+    /// is in this part
+    fn tile_buffer_index(&self, x: u32, y: u32) -> Option<usize> {
+        let (origin_x, origin_y) = self.origin;
+        self.tilemap_layer
+            .tile_buffer_index(x.checked_sub(origin_x)?, y.checked_sub(origin_y)?)
+    }
+}
+
+impl<'a> TileLayerMut<'a> {
+    /// Put together the rendered parts of a layer, returning `None` if there aren't any
+    fn new(
+        parts: impl Iterator<Item = (&'a LdtkTileLayer, Mut<'a, LdtkTilemapLayer>)>,
+    ) -> Option<Self> {
+        let mut sublayers = parts
+            .map(|(layer, tilemap_layer)| {
+                (
+                    (layer.sublayer_index, layer.row.is_some()),
+                    layer.cell_origin(),
+                    tilemap_layer,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if sublayers.is_empty() {
+            return None;
+        }
+
+        // Order the sublayers from lowest to highest, putting the Y-sorted rows of each sublayer
+        // after the rest of it so that new tiles go in the layer itself
+        sublayers.sort_by_key(|(order, _, _)| *order);
+
+        // The layer is as big as all of its chunks put together
+        let size = sublayers
+            .iter()
+            .map(|(_, (x, y), tilemap_layer)| {
+                (
+                    x + tilemap_layer.map_info.width,
+                    y + tilemap_layer.map_info.height,
+                )
+            })
+            .fold((0, 0), |(width, height), (x, y)| {
+                (width.max(x), height.max(y))
+            });
+
+        Some(TileLayerMut {
+            size,
+            sublayers: sublayers
+                .into_iter()
+                .map(|(_, origin, tilemap_layer)| TileLayerPart {
+                    origin,
+                    tilemap_layer,
+                })
+                .collect(),
+        })
+    }
+
+    /// Get the number of cells wide and tall the layer is
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Get the top-most tile in the cell at the given coordinates, with (0, 0) being the top-left
+    /// cell
+    pub fn get_tile(&self, x: u32, y: u32) -> Option<LdtkTilemapTileInfo> {
+        self.sublayers.iter().rev().find_map(|sublayer| {
+            let i = sublayer.tile_buffer_index(x, y)?;
+            Some(sublayer.tilemap_layer.tiles[i]).filter(|tile| !tile.is_empty())
+        })
+    }
+
+    /// Set the tile in the cell at the given coordinates, replacing any tiles that were in the cell
+    ///
+    /// Returns `false` if the coordinates are outside of the layer.
+    pub fn set_tile(&mut self, x: u32, y: u32, tile_index: u32, flip: ldtk::TileFlip) -> bool {
+        if !self.clear_tile(x, y) {
+            return false;
+        }
+
+        // Put the tile in the lowest sublayer
+        let (sublayer, i) = self
+            .sublayers
+            .iter_mut()
+            .find_map(|sublayer| {
+                let i = sublayer.tile_buffer_index(x, y)?;
+                Some((sublayer, i))
+            })
+            .expect("Tile coordinates checked when clearing tile");
+        sublayer.tilemap_layer.tiles[i] = LdtkTilemapTileInfo::new(tile_index, flip);
+
+        true
+    }
+
+    /// Remove all of the tiles in the cell at the given coordinates
+    ///
+    /// Returns `false` if the coordinates are outside of the layer.
+    pub fn clear_tile(&mut self, x: u32, y: u32) -> bool {
+        let mut found = false;
+
+        for sublayer in &mut self.sublayers {
+            let i = match sublayer.tile_buffer_index(x, y) {
+                Some(i) => i,
+                None => continue,
+            };
+            found = true;
+
+            // Only mark sublayers that actually change
+            if !sublayer.tilemap_layer.tiles[i].is_empty() {
+                sublayer.tilemap_layer.tiles[i] = LdtkTilemapTileInfo::EMPTY;
+            }
+        }
+
+        found
+    }
+
+    /// Get how the layer is displayed
+    pub fn display_info(&self) -> LdtkTilemapDisplayInfo {
+        self.sublayers[0].tilemap_layer.display_info
+    }
+
+    /// Set the opacity of the layer, from 0.0 to 1.0
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.update_display_info(|x| x.opacity = opacity);
+    }
+
+    /// Set the color that the tiles of the layer are multiplied by
+    pub fn set_tint(&mut self, tint: Color) {
+        self.update_display_info(|x| x.tint = tint.as_linear_rgba_f32());
+    }
+
+    /// Show or hide the layer
+    pub fn set_visible(&mut self, visible: bool) {
+        self.update_display_info(|x| x.visible = if visible { 1 } else { 0 });
+    }
+
+    /// Update the display info of every sublayer, only marking the sublayers that change
+    fn update_display_info(&mut self, update: impl Fn(&mut LdtkTilemapDisplayInfo)) {
+        for sublayer in &mut self.sublayers {
+            let mut display_info = sublayer.tilemap_layer.display_info;
+            update(&mut display_info);
+
+            if display_info != sublayer.tilemap_layer.display_info {
+                sublayer.tilemap_layer.display_info = display_info;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LdtkTilemapMapInfo;
+
+    use super::*;
+
+    /// Create a rendered part of a layer without any tiles
+    fn tilemap_layer(width: u32, height: u32) -> LdtkTilemapLayer {
+        LdtkTilemapLayer {
+            map_info: LdtkTilemapMapInfo {
+                width,
+                height,
+                ..Default::default()
+            },
+            tiles: vec![LdtkTilemapTileInfo::EMPTY; (width * height) as usize],
+            ..Default::default()
+        }
+    }
+
+    /// Create the component identifying a rendered part of a layer
+    fn tile_layer(
+        sublayer_index: usize,
+        row: Option<u32>,
+        chunk_origin: Option<(u32, u32)>,
+    ) -> LdtkTileLayer {
+        LdtkTileLayer {
+            identifier: "Walls".into(),
+            sublayer_index,
+            row,
+            chunk_origin,
+        }
+    }
+
+    /// Spawn the rendered parts of a layer into a new world
+    fn spawn_parts(parts: Vec<(LdtkTileLayer, LdtkTilemapLayer)>) -> World {
+        let mut world = World::new();
+        world.spawn_batch(parts);
+        world
+    }
+
+    /// Get mutable access to the layer spawned in a world
+    fn layer_mut(world: &mut World) -> TileLayerMut<'_> {
+        let mut query = world.query::<(&LdtkTileLayer, &mut LdtkTilemapLayer)>();
+        TileLayerMut::new(query.iter_mut(world)).unwrap()
+    }
+
+    /// Set a tile directly in a rendered part of a layer
+    fn with_tile(
+        mut tilemap_layer: LdtkTilemapLayer,
+        x: u32,
+        y: u32,
+        tile_index: u32,
+    ) -> LdtkTilemapLayer {
+        let i = tilemap_layer.tile_buffer_index(x, y).unwrap();
+        tilemap_layer.tiles[i] = LdtkTilemapTileInfo {
+            tile_index,
+            flip_bits: 0,
+        };
+        tilemap_layer
+    }
+
+    #[test]
+    fn layer_includes_y_sorted_rows() {
+        let mut world = spawn_parts(vec![
+            (
+                tile_layer(0, None, None),
+                with_tile(tilemap_layer(3, 2), 0, 1, 2),
+            ),
+            (
+                tile_layer(0, Some(1), None),
+                with_tile(tilemap_layer(3, 1), 1, 0, 5),
+            ),
+        ]);
+
+        let mut layer = layer_mut(&mut world);
+        assert_eq!(layer.size(), (3, 2));
+        assert_eq!(layer.get_tile(0, 1).map(|x| x.tile_index), Some(2));
+        assert_eq!(layer.get_tile(1, 1).map(|x| x.tile_index), Some(5));
+
+        // The rows are displayed like the rest of the layer
+        layer.set_opacity(0.5);
+
+        // Replacing a Y-sorted tile puts the new tile in the layer itself
+        assert!(layer.set_tile(1, 1, 7, NO_FLIP));
+        assert_eq!(layer.get_tile(1, 1).map(|x| x.tile_index), Some(7));
+
+        let mut query = world.query::<(&LdtkTileLayer, &LdtkTilemapLayer)>();
+        for (tile_layer, tilemap_layer) in query.iter(&world) {
+            assert_eq!(tilemap_layer.display_info.opacity, 0.5);
+
+            match tile_layer.row {
+                Some(_) => assert_eq!(tilemap_layer.get_tile(1, 0), None),
+                None => assert_eq!(tilemap_layer.get_tile(1, 1).unwrap().tile_index, 7),
+            }
+        }
+    }
+
+    /// Get the tile index in a cell of a layer, if there is a tile
+    fn tile_index(layer: &TileLayerMut, x: u32, y: u32) -> Option<u32> {
+        layer.get_tile(x, y).map(|x| x.tile_index)
+    }
+
+    const NO_FLIP: ldtk::TileFlip = ldtk::TileFlip { x: false, y: false };
+
+    #[test]
+    fn tile_buffer_index_reverses_x() {
+        let layer = tilemap_layer(3, 2);
+
+        assert_eq!(layer.tile_buffer_index(0, 0), Some(2));
+        assert_eq!(layer.tile_buffer_index(2, 0), Some(0));
+        assert_eq!(layer.tile_buffer_index(0, 1), Some(5));
+        assert_eq!(layer.tile_buffer_index(2, 1), Some(3));
+        assert_eq!(layer.tile_buffer_index(3, 0), None);
+        assert_eq!(layer.tile_buffer_index(0, 2), None);
+
+        let layer = with_tile(layer, 1, 1, 8);
+        assert_eq!(layer.tiles[4].tile_index, 8);
+        assert_eq!(layer.get_tile(1, 1).map(|x| x.tile_index), Some(8));
+        assert_eq!(layer.get_tile(1, 0), None);
+        assert_eq!(layer.get_tile(3, 3), None);
+    }
+
+    #[test]
+    fn layer_edits_span_sublayers() {
+        let mut world = spawn_parts(vec![
+            (
+                tile_layer(1, None, None),
+                with_tile(tilemap_layer(2, 2), 0, 0, 2),
+            ),
+            (
+                tile_layer(0, None, None),
+                with_tile(tilemap_layer(2, 2), 0, 0, 1),
+            ),
+        ]);
+        let mut layer = layer_mut(&mut world);
+
+        // The top-most tile is returned
+        assert_eq!(tile_index(&layer, 0, 0), Some(2));
+
+        // Setting a tile replaces the tiles of every sublayer
+        assert!(layer.set_tile(0, 0, 3, NO_FLIP));
+        assert_eq!(tile_index(&layer, 0, 0), Some(3));
+
+        // Clearing a tile clears every sublayer
+        assert!(layer.set_tile(1, 1, 4, NO_FLIP));
+        assert!(layer.clear_tile(0, 0));
+        assert_eq!(tile_index(&layer, 0, 0), None);
+        assert_eq!(tile_index(&layer, 1, 1), Some(4));
+
+        // New tiles go in the lowest sublayer
+        let mut query = world.query::<(&LdtkTileLayer, &LdtkTilemapLayer)>();
+        for (tile_layer, tilemap_layer) in query.iter(&world) {
+            assert_eq!(tilemap_layer.get_tile(0, 0), None);
+
+            let tile = tilemap_layer.get_tile(1, 1).map(|x| x.tile_index);
+            match tile_layer.sublayer_index {
+                0 => assert_eq!(tile, Some(4)),
+                _ => assert_eq!(tile, None),
+            }
+        }
+    }
+
+    #[test]
+    fn layer_edits_span_chunks() {
+        // A 3 by 3 layer split into chunks of 2 by 2 cells
+        let mut world = spawn_parts(vec![
+            (tile_layer(0, None, Some((0, 0))), tilemap_layer(2, 2)),
+            (tile_layer(0, None, Some((2, 0))), tilemap_layer(1, 2)),
+            (tile_layer(0, None, Some((0, 2))), tilemap_layer(2, 1)),
+            (
+                tile_layer(0, None, Some((2, 2))),
+                with_tile(tilemap_layer(1, 1), 0, 0, 6),
+            ),
+        ]);
+        let mut layer = layer_mut(&mut world);
+
+        assert_eq!(layer.size(), (3, 3));
+        assert_eq!(tile_index(&layer, 2, 2), Some(6));
+
+        assert!(layer.set_tile(1, 2, 7, NO_FLIP));
+        assert!(layer.set_tile(2, 1, 8, NO_FLIP));
+        assert_eq!(tile_index(&layer, 1, 2), Some(7));
+        assert_eq!(tile_index(&layer, 2, 1), Some(8));
+
+        // The tiles are set in the chunks that contain them
+        let mut query = world.query::<(&LdtkTileLayer, &LdtkTilemapLayer)>();
+        for (tile_layer, tilemap_layer) in query.iter(&world) {
+            let tile = |x, y| tilemap_layer.get_tile(x, y).map(|x| x.tile_index);
+            match tile_layer.chunk_origin {
+                Some((0, 2)) => assert_eq!(tile(1, 0), Some(7)),
+                Some((2, 0)) => assert_eq!(tile(0, 1), Some(8)),
+                Some((2, 2)) => assert_eq!(tile(0, 0), Some(6)),
+                _ => assert!(tilemap_layer.tiles.iter().all(|x| x.is_empty())),
+            }
+        }
+    }
+
+    #[test]
+    fn layer_edits_outside_of_the_layer_are_ignored() {
+        let mut world = spawn_parts(vec![
+            (tile_layer(0, None, Some((0, 0))), tilemap_layer(2, 2)),
+            (tile_layer(0, None, Some((2, 0))), tilemap_layer(1, 2)),
+        ]);
+        let mut layer = layer_mut(&mut world);
+
+        assert_eq!(layer.size(), (3, 2));
+        assert!(!layer.set_tile(3, 0, 1, NO_FLIP));
+        assert!(!layer.set_tile(0, 2, 1, NO_FLIP));
+        assert!(!layer.clear_tile(3, 2));
+        assert_eq!(layer.get_tile(3, 0), None);
+        assert_eq!(layer.get_tile(u32::MAX, u32::MAX), None);
+    }
+}
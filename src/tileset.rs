@@ -0,0 +1,130 @@
+use bevy::utils::HashMap;
+
+/// The per-tile metadata of a tileset, indexed by tile id
+///
+/// Tile custom data and enum tags aren't in the LDtk 0.8.1 data structures, so they are read
+/// straight from the project JSON when the map is loaded. Tilesets that don't have any are left
+/// empty.
+///
+/// # Example
+///
+/// ```
+/// use bevy_ldtk::*;
+///
+/// let tileset = serde_json::json!({
+///     "uid": 1,
+///     "identifier": "Terrain",
+///     "customData": [{ "tileId": 3, "data": "{ \"friction\": 0.1 }" }],
+///     "enumTags": [{ "enumValueId": "Ice", "tileIds": [3, 4] }],
+/// });
+/// let meta = LdtkTilesetMeta::from_json(&tileset).unwrap();
+///
+/// assert_eq!(meta.custom_data(3), Some("{ \"friction\": 0.1 }"));
+/// assert_eq!(meta.custom_data_json(3).unwrap()["friction"], 0.1);
+/// assert_eq!(meta.enum_tags(4), &["Ice".to_string()]);
+/// assert!(meta.has_enum_tag(3, "Ice"));
+/// assert!(!meta.has_enum_tag(5, "Ice"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LdtkTilesetMeta {
+    /// The unique id of the tileset
+    pub uid: i32,
+    /// The identifier of the tileset
+    pub identifier: String,
+    /// The custom data string of every tile that has custom data
+    pub custom_data: HashMap<u32, String>,
+    /// The custom data of every tile whose custom data is valid JSON, parsed
+    pub custom_data_json: HashMap<u32, serde_json::Value>,
+    /// The enum values that every tagged tile is tagged with
+    pub enum_tags: HashMap<u32, Vec<String>>,
+}
+
+impl LdtkTilesetMeta {
+    /// Read the metadata of a tileset from its tileset definition in the project JSON, returning
+    /// `None` if it isn't a tileset definition
+    ///
+    /// Both the LDtk 0.9 format, where `customData` and `enumTags` are lists, and the older format,
+    /// where they are objects indexed by tile id and enum value, are supported.
+    pub fn from_json(tileset: &serde_json::Value) -> Option<Self> {
+        let mut meta = LdtkTilesetMeta {
+            uid: tileset["uid"].as_i64()? as i32,
+            identifier: tileset["identifier"].as_str()?.into(),
+            ..Default::default()
+        };
+
+        // Read the custom data of the tiles
+        match &tileset["customData"] {
+            serde_json::Value::Array(entries) => {
+                meta.custom_data = entries
+                    .iter()
+                    .filter_map(|x| {
+                        Some((x["tileId"].as_u64()? as u32, x["data"].as_str()?.into()))
+                    })
+                    .collect();
+            }
+            serde_json::Value::Object(entries) => {
+                meta.custom_data = entries
+                    .iter()
+                    .filter_map(|(tile_id, data)| {
+                        Some((tile_id.parse().ok()?, data.as_str()?.into()))
+                    })
+                    .collect();
+            }
+            _ => (),
+        }
+
+        meta.custom_data_json = meta
+            .custom_data
+            .iter()
+            .filter_map(|(&tile_id, data)| Some((tile_id, serde_json::from_str(data).ok()?)))
+            .collect();
+
+        // Read the enum values that the tiles are tagged with
+        let enum_tags: Vec<(&str, &serde_json::Value)> = match &tileset["enumTags"] {
+            serde_json::Value::Array(entries) => entries
+                .iter()
+                .filter_map(|x| Some((x["enumValueId"].as_str()?, &x["tileIds"])))
+                .collect(),
+            serde_json::Value::Object(entries) => entries
+                .iter()
+                .map(|(enum_value, tile_ids)| (enum_value.as_str(), tile_ids))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for (enum_value, tile_ids) in enum_tags {
+            for tile_id in tile_ids.as_array().into_iter().flatten() {
+                if let Some(tile_id) = tile_id.as_u64() {
+                    meta.enum_tags
+                        .entry(tile_id as u32)
+                        .or_default()
+                        .push(enum_value.into());
+                }
+            }
+        }
+
+        Some(meta)
+    }
+
+    /// Get the custom data string of a tile
+    pub fn custom_data(&self, tile_id: u32) -> Option<&str> {
+        self.custom_data.get(&tile_id).map(|x| x.as_str())
+    }
+
+    /// Get the custom data of a tile parsed as JSON, if it is valid JSON
+    pub fn custom_data_json(&self, tile_id: u32) -> Option<&serde_json::Value> {
+        self.custom_data_json.get(&tile_id)
+    }
+
+    /// Get the enum values that a tile is tagged with
+    pub fn enum_tags(&self, tile_id: u32) -> &[String] {
+        self.enum_tags
+            .get(&tile_id)
+            .map(|x| x.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Get whether or not a tile is tagged with the given enum value
+    pub fn has_enum_tag(&self, tile_id: u32, enum_value: &str) -> bool {
+        self.enum_tags(tile_id).iter().any(|x| x == enum_value)
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::LdtkTilesetMeta;
+
+/// Settings for drawing the tiles of some layers sorted by their Y coordinate, for top-down games
+/// where characters should be drawn behind the walls that are lower on the screen than them
+///
+/// The Y-sorted tiles are taken out of their layer and rendered in separate rows, one for each
+/// row of the layer that has Y-sorted tiles. Every row is placed on the Z axis according to the Y
+/// coordinate of the bottom edge of the row relative to the map, with lower rows being drawn on
+/// top. Sprites with an [`LdtkYSorted`] component are placed on the Z axis the same way, so that
+/// they are drawn in between the rows.
+///
+/// The rows are included when editing a layer with [`LdtkTileLayers::layer_mut`], but the rows are
+/// only split out when the map is spawned. Tiles set with [`TileLayerMut::set_tile`] are put in the
+/// layer itself, so they aren't Y-sorted, even if they replace a Y-sorted tile.
+///
+/// [`LdtkTileLayers::layer_mut`]: crate::LdtkTileLayers::layer_mut
+/// [`TileLayerMut::set_tile`]: crate::TileLayerMut::set_tile
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkYSort {
+    /// The layers or tiles that are Y-sorted
+    pub selectors: Vec<YSortSelector>,
+    /// The Z coordinate of things at a Y coordinate of 0 relative to the map
+    pub z_origin: f32,
+    /// How much the Z coordinate goes down for every unit up the Y axis
+    ///
+    /// This should be small enough to keep the Y-sorted tiles and sprites between the layers that
+    /// they should be drawn between, for every Y coordinate in the map.
+    pub z_per_unit: f32,
+}
+
+/// Selects the tiles that are Y-sorted by an [`LdtkYSort`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum YSortSelector {
+    /// All of the tiles in the layer with the given identifier, and the entities spawned from it
+    Layer(String),
+    /// All of the tiles in any layer that are tagged with the given enum value in their tileset
+    EnumTag(String),
+}
+
+impl LdtkYSort {
+    /// Get the Z coordinate of something at the given Y coordinate relative to the map
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bevy_ldtk::*;
+    ///
+    /// let y_sort = LdtkYSort {
+    ///     selectors: vec![YSortSelector::Layer("Walls".into())],
+    ///     z_origin: 5.0,
+    ///     z_per_unit: 0.001,
+    /// };
+    ///
+    /// // Things lower on the screen are drawn on top
+    /// assert!(y_sort.z(-100.0) > y_sort.z(-50.0));
+    /// assert_eq!(y_sort.z(0.0), 5.0);
+    /// ```
+    pub fn z(&self, y: f32) -> f32 {
+        self.z_origin - y * self.z_per_unit
+    }
+
+    /// Get whether or not all of the tiles in the layer with the given identifier are Y-sorted
+    pub fn sorts_layer(&self, identifier: &str) -> bool {
+        self.selectors
+            .iter()
+            .any(|x| matches!(x, YSortSelector::Layer(layer) if layer == identifier))
+    }
+
+    /// Get whether or not a tile is Y-sorted because of its enum tags
+    pub fn sorts_tile(&self, tileset_meta: &LdtkTilesetMeta, tile_id: u32) -> bool {
+        self.selectors.iter().any(|x| match x {
+            YSortSelector::EnumTag(enum_value) => tileset_meta.has_enum_tag(tile_id, enum_value),
+            YSortSelector::Layer(_) => false,
+        })
+    }
+}
+
+/// A component that places an entity on the Z axis according to its Y coordinate, using the
+/// [`LdtkYSort`] settings of a map
+///
+/// Entities spawned from Y-sorted layers get this component automatically. The entity's
+/// translation is only changed along the Z axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LdtkYSorted {
+    /// The map entity to use the Y-sort settings of. When this is `None`, the map is found through
+    /// the entity's parents, which is how the entities spawned from a map are set up.
+    pub map: Option<Entity>,
+}
+
+impl LdtkYSorted {
+    /// Y-sort an entity using the settings of the given map entity
+    pub fn new(map: Entity) -> Self {
+        LdtkYSorted { map: Some(map) }
+    }
+}